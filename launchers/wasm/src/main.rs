@@ -18,11 +18,10 @@ fn view() -> Html {
 }
 
 fn main() {
-    #[cfg(feature = "inspect")]
-    wasm_logger::init(wasm_logger::Config::new(log::Level::Info));
     // Mount the DOM
     yew::Renderer::<Root>::new().render();
-    // Start the Bevy App
+    // Start the Bevy App, the inspector logs to the browser console
+    let mut app = {{crate_name}}::app(false);
     log::info!("Starting launcher: WASM");
-    {{crate_name}}::app(false).run();
+    app.run();
 }
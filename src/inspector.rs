@@ -1,41 +1,219 @@
+use std::borrow::Cow;
+
+use asset_handles::register_engine_handles;
+use bevy::diagnostic::{EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin};
 use bevy::prelude::*;
 use bevy_egui::EguiSet;
 use bevy_inspector_egui::DefaultInspectorConfigPlugin;
 use camera_movement::*;
+use console_commands::ConsoleCommands;
 use default_scene::{set_camera_viewport, setup as setup_scene};
+use diagnostics::{record_diagnostics, DiagnosticsHistory};
+use hierarchy_tree::{update_hierarchy_tree, HierarchyTree};
 use inspect_log::*;
+use logger::{install_subscriber, FanOutLogger, PlatformLogger, SubscriberLayers};
+use play_mode::{begin_step, end_step, setup_play_mode, PlayMode};
+use schedule_graph::{snapshot_last_schedule, snapshot_schedules, ScheduleGraphs};
+use settings::{save_settings, InspectorSettings};
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::Layer;
 use ui::*;
+use undo::UndoHistory;
 
+mod asset_handles;
 mod camera_movement;
+mod console_commands;
 mod default_scene;
+mod diagnostics;
+mod hierarchy_tree;
 mod inspect_log;
+mod logger;
+mod overlays;
+mod picking;
+mod play_mode;
+mod profiler;
+mod scene_io;
+mod schedule_graph;
+mod settings;
 mod ui;
+mod undo;
 
 pub use default_scene::MainGameCamera;
+pub use inspect_log::Logs;
+pub use play_mode::game_running;
+pub use profiler::ProfilerPlugin;
+pub use ui::AddItem;
 
 pub struct InspectorPlugin;
 
 impl Plugin for InspectorPlugin {
     fn build(&self, app: &mut App) {
-        let logger = Logs::default();
-        app.init_resource::<UiState>()
+        // installed first to receive the logs of the settings and of the other plugins, a `Logs`
+        // inserted by the game already receives the records of its logger
+        let logger = match app.world.get_resource::<Logs>() {
+            Some(logs) => logs.clone(),
+            None => {
+                let logs = Logs::default();
+                FanOutLogger::default()
+                    .with(logs.clone())
+                    .with(PlatformLogger::default())
+                    .install();
+                logs
+            }
+        };
+        // Bevy logs with `tracing`, its events are stored in the Console too
+        let events = filter_fn(|metadata| metadata.is_event());
+        SubscriberLayers::add(app, LogLayer(logger.clone()).with_filter(events));
+        let settings = InspectorSettings::load();
+        let mut ui_state = UiState::default();
+        settings.apply(&mut ui_state, &logger);
+
+        app.insert_resource(ui_state)
+            .insert_resource(settings)
+            .init_resource::<UndoHistory>()
+            .init_resource::<PlayMode>()
+            .init_resource::<ConsoleCommands>()
+            .init_resource::<DiagnosticsHistory>()
+            .init_resource::<ScheduleGraphs>()
+            .init_resource::<HierarchyTree>()
             .insert_resource(logger.clone())
             .add_plugins(DefaultInspectorConfigPlugin)
             .add_plugins(bevy_egui::EguiPlugin)
             .add_plugins(bevy_infinite_grid::InfiniteGridPlugin);
-        app.add_systems(Startup, setup_scene)
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin);
+        }
+        if !app.is_plugin_added::<EntityCountDiagnosticsPlugin>() {
+            app.add_plugins(EntityCountDiagnosticsPlugin);
+        }
+        if !app.is_plugin_added::<ProfilerPlugin>() {
+            app.add_plugins(ProfilerPlugin);
+        }
+        app.add_systems(Startup, (setup_scene, setup_play_mode))
+            .add_systems(
+                First,
+                (
+                    begin_step.before(bevy::time::TimeSystem),
+                    update_log_frame,
+                    snapshot_last_schedule,
+                ),
+            )
+            .add_systems(
+                Last,
+                (
+                    end_step,
+                    save_settings,
+                    record_diagnostics,
+                    snapshot_schedules,
+                ),
+            )
             .add_systems(
                 PostUpdate,
                 show_ui_system
+                    .after(update_hierarchy_tree)
                     .before(EguiSet::ProcessOutput)
                     .before(bevy::transform::TransformSystem::TransformPropagate),
             )
+            .add_systems(PostUpdate, update_hierarchy_tree)
             .add_systems(PostUpdate, set_camera_viewport.after(show_ui_system))
             .add_systems(PostUpdate, (camera_movement, camera_look))
+            .register_type::<MainGameCamera>()
             .register_type::<Option<Handle<Image>>>()
             .register_type::<AlphaMode>();
+    }
+
+    fn finish(&self, app: &mut App) {
+        install_subscriber(app);
+        let custom = app
+            .world
+            .remove_resource::<CustomAddItems>()
+            .unwrap_or_default();
+        // every plugin has registered its types at this point
+        let type_registry = app.world.resource::<AppTypeRegistry>().clone();
+        register_engine_handles(&mut type_registry.write());
+
+        let mut ui_state = app.world.resource_mut::<UiState>();
+        let state = &mut ui_state.hierarchy_state;
+        state.set_order(custom.order);
+        for (section, item) in custom.items {
+            state.add(section, item);
+        }
+        state.add_registered_components(&type_registry.read());
+    }
+}
+
+/// Items registered by the game for the "Add" menus, merged by [`InspectorPlugin`].
+#[derive(Resource, Default)]
+struct CustomAddItems {
+    items: Vec<(Cow<'static, str>, AddItem)>,
+    order: Vec<Cow<'static, str>>,
+}
+
+pub trait InspectorAppExt {
+    /// Add an item to a section of the "Add" menus, an empty section places it at the top level.
+    ///
+    /// ```ignore
+    /// app.register_inspector_add_item("Enemies", AddItem::bundle::<GoblinBundle>());
+    /// ```
+    fn register_inspector_add_item(
+        &mut self,
+        section: impl Into<Cow<'static, str>>,
+        item: AddItem,
+    ) -> &mut Self;
+
+    /// Show these sections first, in the given order, in the "Add" menus.
+    fn set_inspector_add_section_order<S: Into<Cow<'static, str>>>(
+        &mut self,
+        sections: impl IntoIterator<Item = S>,
+    ) -> &mut Self;
+
+    /// Add a command to the Console, called with the words typed after its name.
+    ///
+    /// ```ignore
+    /// app.add_console_command("spawn_enemy", |world, args| {
+    ///     let count: usize = args.first().unwrap_or(&"1").parse().map_err(|_| "invalid count")?;
+    ///     world.spawn_batch((0..count).map(|_| EnemyBundle::default()));
+    ///     Ok(())
+    /// });
+    /// ```
+    fn add_console_command(
+        &mut self,
+        name: impl Into<String>,
+        command: impl Fn(&mut World, &[&str]) -> Result<(), String> + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+
+impl InspectorAppExt for App {
+    fn register_inspector_add_item(
+        &mut self,
+        section: impl Into<Cow<'static, str>>,
+        item: AddItem,
+    ) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(CustomAddItems::default)
+            .items
+            .push((section.into(), item));
+        self
+    }
+
+    fn set_inspector_add_section_order<S: Into<Cow<'static, str>>>(
+        &mut self,
+        sections: impl IntoIterator<Item = S>,
+    ) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(CustomAddItems::default)
+            .order = sections.into_iter().map(Into::into).collect();
+        self
+    }
 
-        log::set_max_level(log::LevelFilter::Trace);
-        log::set_boxed_logger(Box::from(logger)).unwrap();
+    fn add_console_command(
+        &mut self,
+        name: impl Into<String>,
+        command: impl Fn(&mut World, &[&str]) -> Result<(), String> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(ConsoleCommands::default)
+            .add(name.into(), command);
+        self
     }
 }
@@ -0,0 +1,204 @@
+use std::any::TypeId;
+use std::collections::BTreeSet;
+
+use bevy::asset::{Asset, HandleUntyped, ReflectHandle};
+use bevy::prelude::*;
+use bevy::render::mesh::skinning::SkinnedMeshInverseBindposes;
+use bevy_reflect::{ReflectMut, ReflectRef, TypeRegistry};
+
+/// Prefix of the type name of every [`Handle`].
+const HANDLE_TYPE_NAME: &str = "bevy_asset::handle::Handle<";
+
+/// Add [`ReflectHandle`] to the handles of the engine assets that aren't reflected, the other
+/// assets get it from `register_asset_reflect`.
+pub fn register_engine_handles(type_registry: &mut TypeRegistry) {
+    register_handle::<Mesh>(type_registry);
+    register_handle::<SkinnedMeshInverseBindposes>(type_registry);
+    register_handle::<Scene>(type_registry);
+    register_handle::<DynamicScene>(type_registry);
+}
+
+fn register_handle<A: Asset>(type_registry: &mut TypeRegistry) {
+    let type_id = TypeId::of::<Handle<A>>();
+    if type_registry.get(type_id).is_some()
+        && type_registry
+            .get_type_data::<ReflectHandle>(type_id)
+            .is_none()
+    {
+        type_registry.register_type_data::<Handle<A>, ReflectHandle>();
+    }
+}
+
+/// Fields, items or values of `value`, none if it is a value type.
+pub fn fields(value: &dyn Reflect) -> Vec<&dyn Reflect> {
+    match value.reflect_ref() {
+        ReflectRef::Struct(value) => (0..value.field_len())
+            .filter_map(|index| value.field_at(index))
+            .collect(),
+        ReflectRef::TupleStruct(value) => (0..value.field_len())
+            .filter_map(|index| value.field(index))
+            .collect(),
+        ReflectRef::Tuple(value) => (0..value.field_len())
+            .filter_map(|index| value.field(index))
+            .collect(),
+        ReflectRef::List(value) => value.iter().collect(),
+        ReflectRef::Array(value) => value.iter().collect(),
+        ReflectRef::Map(value) => value.iter().map(|(_, value)| value).collect(),
+        ReflectRef::Enum(value) => (0..value.field_len())
+            .filter_map(|index| value.field_at(index))
+            .collect(),
+        ReflectRef::Value(_) => Vec::new(),
+    }
+}
+
+fn for_each_field_mut(value: &mut dyn Reflect, mut f: impl FnMut(&mut dyn Reflect)) {
+    match value.reflect_mut() {
+        ReflectMut::Struct(value) => {
+            for index in 0..value.field_len() {
+                if let Some(field) = value.field_at_mut(index) {
+                    f(field);
+                }
+            }
+        }
+        ReflectMut::TupleStruct(value) => {
+            for index in 0..value.field_len() {
+                if let Some(field) = value.field_mut(index) {
+                    f(field);
+                }
+            }
+        }
+        ReflectMut::Tuple(value) => {
+            for index in 0..value.field_len() {
+                if let Some(field) = value.field_mut(index) {
+                    f(field);
+                }
+            }
+        }
+        ReflectMut::List(value) => {
+            for index in 0..value.len() {
+                if let Some(field) = value.get_mut(index) {
+                    f(field);
+                }
+            }
+        }
+        ReflectMut::Array(value) => {
+            for index in 0..value.len() {
+                if let Some(field) = value.get_mut(index) {
+                    f(field);
+                }
+            }
+        }
+        ReflectMut::Map(value) => {
+            for index in 0..value.len() {
+                if let Some((_, field)) = value.get_at_mut(index) {
+                    f(field);
+                }
+            }
+        }
+        ReflectMut::Enum(value) => {
+            for index in 0..value.field_len() {
+                if let Some(field) = value.field_at_mut(index) {
+                    f(field);
+                }
+            }
+        }
+        ReflectMut::Value(_) => {}
+    }
+}
+
+/// `true` if `value` is or contains a [`Handle`].
+pub fn contains_handle(value: &dyn Reflect) -> bool {
+    value.type_name().starts_with(HANDLE_TYPE_NAME)
+        || fields(value).into_iter().any(contains_handle)
+}
+
+/// Clone the strong handles inside the concrete `value`, to keep their assets alive.
+pub fn strong_handles(
+    type_registry: &TypeRegistry,
+    value: &dyn Reflect,
+    handles: &mut Vec<HandleUntyped>,
+) {
+    let type_id = value.as_any().type_id();
+    if let Some(reflect_handle) = type_registry.get_type_data::<ReflectHandle>(type_id) {
+        let handle = reflect_handle.downcast_handle_untyped(value.as_any());
+        handles.extend(handle.filter(HandleUntyped::is_strong));
+        return;
+    }
+    for field in fields(value) {
+        strong_handles(type_registry, field, handles);
+    }
+}
+
+/// Make strong again the handles of entities rebuilt from reflected components.
+///
+/// Reflection only keeps the id of a [`Handle`], so the components recreated from a snapshot or
+/// a scene hold weak handles, and their assets are freed once the original handles are dropped.
+pub fn strengthen_handles(world: &mut World, entities: impl IntoIterator<Item = Entity>) {
+    let Some(asset_server) = world.get_resource::<AssetServer>().cloned() else {
+        return;
+    };
+    let type_registry = world.resource::<AppTypeRegistry>().clone();
+    let type_registry = type_registry.read();
+    let mut unsupported = BTreeSet::new();
+
+    for entity in entities {
+        let Some(entity_ref) = world.get_entity(entity) else {
+            continue;
+        };
+        let reflect_components: Vec<ReflectComponent> = entity_ref
+            .archetype()
+            .components()
+            .filter_map(|component_id| world.components().get_info(component_id)?.type_id())
+            .filter_map(|type_id| type_registry.get_type_data::<ReflectComponent>(type_id))
+            .cloned()
+            .collect();
+        let mut entity_mut = world.entity_mut(entity);
+        for reflect_component in reflect_components {
+            let Some(mut component) = reflect_component.reflect_mut(&mut entity_mut) else {
+                continue;
+            };
+            // the ids don't change, the component isn't modified
+            strengthen(
+                &type_registry,
+                &asset_server,
+                component.bypass_change_detection(),
+                &mut unsupported,
+            );
+        }
+    }
+
+    for type_name in unsupported {
+        log::warn!("{type_name} can't be restored as a strong handle, its asset may be freed");
+    }
+}
+
+fn strengthen(
+    type_registry: &TypeRegistry,
+    asset_server: &AssetServer,
+    value: &mut dyn Reflect,
+    unsupported: &mut BTreeSet<String>,
+) {
+    let type_id = value.as_any().type_id();
+    let Some(reflect_handle) = type_registry.get_type_data::<ReflectHandle>(type_id) else {
+        if value.type_name().starts_with(HANDLE_TYPE_NAME) {
+            unsupported.insert(value.type_name().to_string());
+        } else {
+            for_each_field_mut(value, |field| {
+                strengthen(type_registry, asset_server, field, unsupported)
+            });
+        }
+        return;
+    };
+    let Some(handle) = reflect_handle.downcast_handle_untyped(value.as_any()) else {
+        return;
+    };
+    if handle.is_strong() {
+        return;
+    }
+    // an asset loaded from a file is loaded again if it was freed
+    let strong = match asset_server.get_handle_path(handle.id()) {
+        Some(path) => asset_server.load_untyped(path.to_owned()),
+        None => asset_server.get_handle_untyped(handle.id()),
+    };
+    let _ = value.set(reflect_handle.typed(strong));
+}
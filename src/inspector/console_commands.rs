@@ -0,0 +1,516 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use bevy::hierarchy::despawn_with_children_recursive;
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_inspector::hierarchy::SelectedEntities;
+use bevy_reflect::serde::TypedReflectDeserializer;
+use bevy_reflect::{GetPath, ReflectRef, TypeRegistration, TypeRegistry};
+use serde::de::DeserializeSeed;
+
+use super::ui::AddWindowState;
+use super::undo::PendingEdit;
+
+/// Command of the Console, run with the words typed after its name.
+pub type ConsoleCommandFn = dyn Fn(&mut World, &[&str]) -> Result<(), String> + Send + Sync;
+
+/// Commands registered by the game with [`InspectorAppExt::add_console_command`].
+///
+/// [`InspectorAppExt::add_console_command`]: super::InspectorAppExt::add_console_command
+#[derive(Resource, Default)]
+pub struct ConsoleCommands {
+    commands: BTreeMap<String, Arc<ConsoleCommandFn>>,
+}
+
+impl ConsoleCommands {
+    pub fn add(
+        &mut self,
+        name: String,
+        command: impl Fn(&mut World, &[&str]) -> Result<(), String> + Send + Sync + 'static,
+    ) {
+        if BUILTINS.iter().any(|(builtin, _)| *builtin == name) {
+            log::warn!("The console command `{name}` is hidden by the built-in one");
+        }
+        self.commands.insert(name, Arc::new(command));
+    }
+}
+
+/// Commands of the inspector, with their usage.
+const BUILTINS: [(&str, &str); 6] = [
+    ("help", "help: list the commands"),
+    ("spawn", "spawn <item>: spawn an item of the Add menu"),
+    (
+        "despawn",
+        "despawn <entity>: despawn an entity and its children, e.g. `despawn 12v0`",
+    ),
+    (
+        "select",
+        "select <name>: select the entities with this name",
+    ),
+    (
+        "timescale",
+        "timescale <factor>: speed of the game time, 1 is the normal speed",
+    ),
+    (
+        "set",
+        "set <Resource>.<field> <value>: set a reflected resource field to a RON value",
+    ),
+];
+
+/// Run a command line, its output and errors are logged.
+///
+/// Returns `true` if the entity selection was changed.
+pub fn run_command(
+    world: &mut World,
+    selected: &mut SelectedEntities,
+    add_items: &AddWindowState,
+    line: &str,
+) -> bool {
+    log::info!("> {line}");
+    let words = split_words(line);
+    let Some((name, args)) = words.split_first() else {
+        return false;
+    };
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match execute(world, selected, add_items, name, &args) {
+        Ok(selection_changed) => selection_changed,
+        Err(err) => {
+            log::error!("{name}: {err}");
+            false
+        }
+    }
+}
+
+/// Run the command `name`, returns `true` if the entity selection was changed.
+fn execute(
+    world: &mut World,
+    selected: &mut SelectedEntities,
+    add_items: &AddWindowState,
+    name: &str,
+    args: &[&str],
+) -> Result<bool, String> {
+    match name {
+        "help" => {
+            help(world);
+            Ok(false)
+        }
+        "spawn" => spawn(world, add_items, &args.join(" ")).map(|entity| {
+            selected.select_replace(entity);
+            true
+        }),
+        "despawn" => despawn(world, args).map(|()| false),
+        "select" => select(world, &args.join(" ")).map(|entities| {
+            selected.clear();
+            for entity in entities {
+                selected.select_maybe_add(entity, true);
+            }
+            true
+        }),
+        "timescale" => timescale(world, args).map(|()| false),
+        "set" => set_resource(world, args).map(|()| false),
+        _ => {
+            let command = world
+                .resource::<ConsoleCommands>()
+                .commands
+                .get(name)
+                .cloned();
+            match command {
+                Some(command) => command(world, args).map(|()| false),
+                None => Err("unknown command, see `help`".to_string()),
+            }
+        }
+    }
+}
+
+/// Words of a command line, double quotes group several words.
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn help(world: &World) {
+    let mut help = String::from("Commands:");
+    for (_, usage) in BUILTINS {
+        help.push_str("\n  ");
+        help.push_str(usage);
+    }
+    for name in world.resource::<ConsoleCommands>().commands.keys() {
+        help.push_str("\n  ");
+        help.push_str(name);
+    }
+    log::info!("{help}");
+}
+
+fn spawn(world: &mut World, add_items: &AddWindowState, name: &str) -> Result<Entity, String> {
+    let item = add_items
+        .sections()
+        .flat_map(|(_, items)| items)
+        .find(|item| item.name().eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("no item named `{name}` in the Add menu"))?;
+
+    let mut edit = PendingEdit::begin(world, format!("Add {}", item.name()), []);
+    let entity = world.spawn_empty().id();
+    item.add_to_entity(world, entity);
+    edit.track(entity);
+    edit.commit(world);
+    log::info!("Spawned {} as {entity:?}", item.name());
+    Ok(entity)
+}
+
+fn despawn(world: &mut World, args: &[&str]) -> Result<(), String> {
+    let &[entity] = args else {
+        return Err("expected an entity, e.g. `despawn 12v0`".to_string());
+    };
+    let entity = parse_entity(world, entity)?;
+
+    let mut entities = vec![entity];
+    let mut index = 0;
+    while let Some(&parent) = entities.get(index) {
+        if let Some(children) = world.get::<Children>(parent) {
+            entities.extend(children.iter().copied());
+        }
+        index += 1;
+    }
+    let edit = PendingEdit::begin(world, "Despawn", entities);
+    despawn_with_children_recursive(world, entity);
+    edit.commit(world);
+    log::info!("Despawned {entity:?}");
+    Ok(())
+}
+
+/// An existing entity written as `index` or `indexvgeneration`, like its `Debug` output.
+fn parse_entity(world: &World, text: &str) -> Result<Entity, String> {
+    let invalid = || format!("`{text}` isn't an entity, e.g. `12v0`");
+    let (index, generation) = match text.split_once('v') {
+        Some((index, generation)) => (index, Some(generation)),
+        None => (text, None),
+    };
+    let index: u32 = index.parse().map_err(|_| invalid())?;
+    let generation: Option<u32> = generation
+        .map(|generation| generation.parse().map_err(|_| invalid()))
+        .transpose()?;
+    world
+        .iter_entities()
+        .map(|entity| entity.id())
+        .find(|entity| {
+            entity.index() == index && generation.iter().all(|&g| entity.generation() == g)
+        })
+        .ok_or_else(|| format!("no entity {text}"))
+}
+
+fn select(world: &World, name: &str) -> Result<Vec<Entity>, String> {
+    let entities: Vec<Entity> = world
+        .iter_entities()
+        .filter(|entity| {
+            entity
+                .get::<Name>()
+                .is_some_and(|entity_name| entity_name.as_str() == name)
+        })
+        .map(|entity| entity.id())
+        .collect();
+    if entities.is_empty() {
+        return Err(format!("no entity named `{name}`"));
+    }
+    log::info!("Selected {} entities", entities.len());
+    Ok(entities)
+}
+
+fn timescale(world: &mut World, args: &[&str]) -> Result<(), String> {
+    let &[factor] = args else {
+        return Err("expected a factor, e.g. `timescale 0.5`".to_string());
+    };
+    let factor: f32 = factor
+        .parse()
+        .ok()
+        .filter(|factor: &f32| factor.is_finite() && *factor >= 0.0)
+        .ok_or_else(|| format!("`{factor}` isn't a positive number"))?;
+    world.resource_mut::<Time>().set_relative_speed(factor);
+    log::info!("Time scale set to {factor}");
+    Ok(())
+}
+
+fn set_resource(world: &mut World, args: &[&str]) -> Result<(), String> {
+    let [path, value @ ..] = args else {
+        return Err(
+            "expected a resource field and a value, e.g. `set ClearColor.0 Rgba(..)`".into(),
+        );
+    };
+    if value.is_empty() {
+        return Err(format!("expected a value for `{path}`"));
+    }
+    let value = value.join(" ");
+    let (resource_name, field_path) = match path.split_once('.') {
+        Some((resource_name, field_path)) => (resource_name, Some(field_path)),
+        None => (*path, None),
+    };
+
+    let type_registry = world.resource::<AppTypeRegistry>().clone();
+    let type_registry = type_registry.read();
+    let reflect_resource = find_resource(&type_registry, resource_name)
+        .and_then(|registration| registration.data::<ReflectResource>())
+        .ok_or_else(|| format!("no reflected resource named `{resource_name}`"))?;
+    let resource = reflect_resource
+        .reflect_mut(world)
+        .ok_or_else(|| format!("`{resource_name}` isn't in the world"))?
+        .into_inner();
+    let field = match field_path {
+        Some(field_path) => resource
+            .reflect_path_mut(field_path)
+            .map_err(|err| err.to_string())?,
+        None => resource,
+    };
+
+    let type_name = field.type_name();
+    let registration = type_registry
+        .get_with_name(type_name)
+        .ok_or_else(|| format!("`{type_name}` isn't registered"))?;
+    let mut deserializer = ron::Deserializer::from_str(&value).map_err(|err| err.to_string())?;
+    let parsed = TypedReflectDeserializer::new(registration, &type_registry)
+        .deserialize(&mut deserializer)
+        .map_err(|err| format!("`{value}` isn't a valid {type_name}: {err}"))?;
+    field.apply(&*parsed);
+    log::info!("{path} = {value}");
+    Ok(())
+}
+
+/// Registration of a resource from its short or full type name.
+fn find_resource<'a>(type_registry: &'a TypeRegistry, name: &str) -> Option<&'a TypeRegistration> {
+    type_registry.iter().find(|registration| {
+        registration.data::<ReflectResource>().is_some()
+            && (registration.short_name() == name || registration.type_name() == name)
+    })
+}
+
+/// Replacements of the end of a command line, from the byte `start`.
+pub struct Completions {
+    pub start: usize,
+    pub candidates: Vec<String>,
+}
+
+/// Complete the command name or its argument.
+pub fn complete(world: &World, add_items: &AddWindowState, line: &str) -> Completions {
+    let trimmed = line.trim_start();
+    let Some((name, arg)) = trimmed.split_once(char::is_whitespace) else {
+        let commands = world.resource::<ConsoleCommands>().commands.keys();
+        let names = BUILTINS
+            .iter()
+            .map(|(name, _)| *name)
+            .chain(commands.map(String::as_str));
+        return Completions {
+            start: line.len() - trimmed.len(),
+            candidates: matching(names, trimmed),
+        };
+    };
+    let arg = arg.trim_start();
+    let start = line.len() - arg.len();
+
+    let candidates = match name {
+        "spawn" => matching(
+            add_items
+                .sections()
+                .flat_map(|(_, items)| items)
+                .map(|item| item.name()),
+            arg,
+        ),
+        "select" => matching(
+            world
+                .iter_entities()
+                .filter_map(|entity| entity.get::<Name>())
+                .map(Name::as_str),
+            arg,
+        ),
+        "despawn" => {
+            let entities: Vec<String> = world
+                .iter_entities()
+                .map(|entity| format!("{:?}", entity.id()))
+                .collect();
+            matching(entities.iter().map(String::as_str), arg)
+        }
+        "set" => resource_paths(world, arg),
+        _ => Vec::new(),
+    };
+    Completions { start, candidates }
+}
+
+/// The sorted and deduplicated `names` starting with `prefix`, ignoring the case.
+fn matching<'a>(names: impl Iterator<Item = &'a str>, prefix: &str) -> Vec<String> {
+    let prefix = prefix.to_lowercase();
+    let mut names: Vec<String> = names
+        .filter(|name| name.to_lowercase().starts_with(&prefix))
+        .map(str::to_string)
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Resources in the world, or the fields of the one before the last `.` of `path`.
+fn resource_paths(world: &World, path: &str) -> Vec<String> {
+    let type_registry = world.resource::<AppTypeRegistry>().clone();
+    let type_registry = type_registry.read();
+
+    let Some((parent, field)) = path.rsplit_once('.') else {
+        let resources: Vec<&str> = type_registry
+            .iter()
+            .filter(|registration| {
+                let reflect_resource = registration.data::<ReflectResource>();
+                reflect_resource.is_some_and(|resource| resource.reflect(world).is_some())
+            })
+            .map(|registration| registration.short_name())
+            .collect();
+        return matching(resources.into_iter(), path);
+    };
+
+    let (resource_name, parent_path) = match parent.split_once('.') {
+        Some((resource_name, parent_path)) => (resource_name, Some(parent_path)),
+        None => (parent, None),
+    };
+    let resource = find_resource(&type_registry, resource_name)
+        .and_then(|registration| registration.data::<ReflectResource>())
+        .and_then(|reflect_resource| reflect_resource.reflect(world));
+    let value = match (resource, parent_path) {
+        (Some(resource), Some(parent_path)) => resource.reflect_path(parent_path).ok(),
+        (resource, _) => resource,
+    };
+    let fields: Vec<String> = match value.map(Reflect::reflect_ref) {
+        Some(ReflectRef::Struct(value)) => (0..value.field_len())
+            .filter_map(|index| value.name_at(index))
+            .map(str::to_string)
+            .collect(),
+        Some(ReflectRef::TupleStruct(value)) => (0..value.field_len())
+            .map(|index| index.to_string())
+            .collect(),
+        _ => Vec::new(),
+    };
+    matching(fields.iter().map(String::as_str), field)
+        .into_iter()
+        .map(|field| format!("{parent}.{field}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inspector::undo::UndoHistory;
+
+    #[derive(Resource, Reflect, Default)]
+    #[reflect(Resource)]
+    struct Settings {
+        speed: f32,
+    }
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<AppTypeRegistry>();
+        world.init_resource::<ConsoleCommands>();
+        world.init_resource::<UndoHistory>();
+        world.init_resource::<Time>();
+        world.init_resource::<Settings>();
+        world
+            .resource::<AppTypeRegistry>()
+            .write()
+            .register::<Settings>();
+        world
+    }
+
+    /// Run `line` like the Console, but return the error instead of logging it.
+    fn run(world: &mut World, line: &str) -> Result<bool, String> {
+        let words = split_words(line);
+        let (name, args) = words.split_first().unwrap();
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let mut selected = SelectedEntities::default();
+        execute(
+            world,
+            &mut selected,
+            &AddWindowState::default(),
+            name,
+            &args,
+        )
+    }
+
+    #[test]
+    fn quotes_group_words() {
+        assert_eq!(
+            split_words("  select  Main Camera "),
+            ["select", "Main", "Camera"]
+        );
+        assert_eq!(
+            split_words("select \"Main  Camera\""),
+            ["select", "Main  Camera"]
+        );
+        assert_eq!(split_words("a\"b c\"d \"\""), ["ab cd"]);
+        // an unclosed quote runs to the end of the line
+        assert_eq!(
+            split_words("select \"Main Camera"),
+            ["select", "Main Camera"]
+        );
+        assert!(split_words("   ").is_empty());
+    }
+
+    #[test]
+    fn commands_get_their_quoted_arguments() {
+        let mut world = world();
+        world
+            .resource_mut::<ConsoleCommands>()
+            .add("echo".to_string(), |_, args| match args {
+                ["a b", "c"] => Ok(()),
+                args => Err(format!("{args:?}")),
+            });
+        assert_eq!(run(&mut world, "echo \"a b\" c"), Ok(false));
+        assert_eq!(
+            run(&mut world, "unknown"),
+            Err("unknown command, see `help`".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_arguments_are_reported() {
+        let mut world = world();
+        let entity = world.spawn(Name::new("Main Camera")).id();
+
+        assert!(run(&mut world, "despawn").is_err());
+        assert!(run(&mut world, "despawn 1 2").is_err());
+        assert_eq!(
+            run(&mut world, "despawn 12x"),
+            Err("`12x` isn't an entity, e.g. `12v0`".to_string())
+        );
+        assert_eq!(
+            run(&mut world, "despawn 0v1"),
+            Err("no entity 0v1".to_string())
+        );
+        assert!(run(&mut world, "select Camera").is_err());
+        assert!(run(&mut world, "timescale").is_err());
+        assert!(run(&mut world, "timescale -1").is_err());
+        assert!(run(&mut world, "timescale fast").is_err());
+        assert!(run(&mut world, "set").is_err());
+        assert!(run(&mut world, "set Settings.speed").is_err());
+        assert!(run(&mut world, "set Missing.speed 1.0").is_err());
+        assert!(run(&mut world, "set Settings.missing 1.0").is_err());
+        assert!(run(&mut world, "set Settings.speed fast").is_err());
+        assert!(world.get_entity(entity).is_some());
+
+        assert_eq!(run(&mut world, "select \"Main Camera\""), Ok(true));
+        assert_eq!(run(&mut world, "timescale 0.5"), Ok(false));
+        assert_eq!(world.resource::<Time>().relative_speed(), 0.5);
+        assert_eq!(run(&mut world, "set Settings.speed 2.5"), Ok(false));
+        assert_eq!(world.resource::<Settings>().speed, 2.5);
+        assert_eq!(run(&mut world, &format!("despawn {entity:?}")), Ok(false));
+        assert!(world.get_entity(entity).is_none());
+    }
+}
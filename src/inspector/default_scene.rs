@@ -7,12 +7,14 @@ use bevy_egui::EguiUserTextures;
 use bevy_infinite_grid::{GridShadowCamera, InfiniteGridBundle};
 
 use super::camera_movement::FlycamControls;
+use super::settings::InspectorSettings;
 use super::ui::UiState;
 
 #[derive(Component)]
 pub struct MainSceneCamera;
 
-#[derive(Component)]
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
 pub struct MainGameCamera;
 
 #[derive(Component)]
@@ -52,6 +54,7 @@ pub fn setup(
     mut egui_user_textures: ResMut<EguiUserTextures>,
     mut images: ResMut<Assets<Image>>,
     mut ui_state: ResMut<UiState>,
+    settings: Res<InspectorSettings>,
 ) {
     //
     // Prepare render Scene image
@@ -63,8 +66,7 @@ pub fn setup(
     // camera
     commands.spawn((
         Camera3dBundle {
-            transform: Transform::from_translation(Vec3::new(0., 3., 10.))
-                .with_rotation(Quat::from_rotation_x(-0.2)),
+            transform: settings.flycam.transform(),
             camera: Camera {
                 target: bevy::render::camera::RenderTarget::Image(image_scene_handle.clone()),
                 ..default()
@@ -72,7 +74,11 @@ pub fn setup(
             ..default()
         },
         InspectorEntity,
-        FlycamControls::default(),
+        FlycamControls {
+            yaw: settings.flycam.yaw,
+            pitch: settings.flycam.pitch,
+            ..default()
+        },
         MainSceneCamera,
         GridShadowCamera,
     ));
@@ -0,0 +1,68 @@
+use std::collections::VecDeque;
+
+use bevy::diagnostic::{DiagnosticId, DiagnosticsStore};
+use bevy::prelude::*;
+use bevy::utils::{HashMap, Instant};
+
+/// Number of measurements kept for each diagnostic.
+pub const HISTORY_LENGTH: usize = 300;
+
+/// Recent measurements of every diagnostic, longer than the history of [`DiagnosticsStore`].
+#[derive(Resource, Default)]
+pub struct DiagnosticsHistory {
+    series: HashMap<DiagnosticId, Series>,
+}
+
+/// Measurements of a diagnostic, as seconds since the app started and their value.
+#[derive(Default)]
+pub struct Series {
+    points: VecDeque<[f64; 2]>,
+    last_measurement: Option<Instant>,
+}
+
+impl Series {
+    pub fn points(&self) -> impl Iterator<Item = [f64; 2]> + '_ {
+        self.points.iter().copied()
+    }
+
+    /// Minimum, average and maximum of the kept measurements.
+    pub fn stats(&self) -> Option<(f64, f64, f64)> {
+        if self.points.is_empty() {
+            return None;
+        }
+        let values = self.points.iter().map(|[_, value]| *value);
+        let min = values.clone().fold(f64::INFINITY, f64::min);
+        let max = values.clone().fold(f64::NEG_INFINITY, f64::max);
+        let average = values.sum::<f64>() / self.points.len() as f64;
+        Some((min, average, max))
+    }
+}
+
+impl DiagnosticsHistory {
+    pub fn get(&self, id: DiagnosticId) -> Option<&Series> {
+        self.series.get(&id)
+    }
+}
+
+/// Copy the new measurements of the enabled diagnostics into [`DiagnosticsHistory`].
+pub fn record_diagnostics(
+    store: Res<DiagnosticsStore>,
+    time: Res<Time>,
+    mut history: ResMut<DiagnosticsHistory>,
+) {
+    let now = time.raw_elapsed_seconds_f64();
+    for diagnostic in store.iter().filter(|diagnostic| diagnostic.is_enabled) {
+        let Some(measurement) = diagnostic.measurement() else {
+            continue;
+        };
+        let series = history.series.entry(diagnostic.id).or_default();
+        if series.last_measurement == Some(measurement.time) {
+            continue;
+        }
+        series.last_measurement = Some(measurement.time);
+        if series.points.len() >= HISTORY_LENGTH {
+            series.points.pop_front();
+        }
+        series.points.push_back([now, measurement.value]);
+    }
+}
@@ -0,0 +1,245 @@
+use bevy::ecs::archetype::{Archetype, ArchetypeId, Archetypes};
+use bevy::ecs::component::{ComponentId, Components};
+use bevy::ecs::entity::Entities;
+use bevy::ecs::event::ManualEventReader;
+use bevy::ecs::removal_detection::{RemovedComponentEntity, RemovedComponentEvents};
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+
+use crate::inspector::default_scene::InspectorEntity;
+
+/// Root entities of the Hierarchy, and the changes of the hierarchy.
+///
+/// The Hierarchy only rebuilds the rows of the entities changed since it was last drawn, instead
+/// of walking the world every frame.
+#[derive(Resource, Default)]
+pub struct HierarchyTree {
+    /// Sorted, without the entities of the editor.
+    roots: Vec<Entity>,
+    generation: u64,
+    /// Entities spawned, despawned, reparented or whose children changed in the last generation.
+    moved: Vec<Entity>,
+    /// Entities renamed in the last generation.
+    renamed: Vec<Entity>,
+    /// Archetype of each root, to find the archetypes the roots leave.
+    root_archetypes: HashMap<Entity, ArchetypeId>,
+    /// Roots of each archetype and its length when it was last scanned.
+    archetype_roots: HashMap<ArchetypeId, (usize, Vec<Entity>)>,
+}
+
+impl HierarchyTree {
+    pub fn roots(&self) -> &[Entity] {
+        &self.roots
+    }
+
+    pub fn is_root(&self, entity: Entity) -> bool {
+        self.roots.binary_search(&entity).is_ok()
+    }
+
+    /// Changes when an entity was spawned, despawned, reparented or renamed.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn moved(&self) -> &[Entity] {
+        &self.moved
+    }
+
+    pub fn renamed(&self) -> &[Entity] {
+        &self.renamed
+    }
+}
+
+impl HierarchyTree {
+    fn insert_root(&mut self, root: Entity) {
+        if let Err(index) = self.roots.binary_search(&root) {
+            self.roots.insert(index, root);
+        }
+    }
+
+    fn remove_root(&mut self, root: Entity) {
+        if let Ok(index) = self.roots.binary_search(&root) {
+            self.roots.remove(index);
+        }
+    }
+
+    /// Compare the roots of `archetype` with its roots when it was last scanned, and return the
+    /// archetypes to scan next: those left by the roots found here, and those the roots gone
+    /// from here moved to.
+    fn scan(
+        &mut self,
+        archetype: &Archetype,
+        entities: &Entities,
+        has_roots: impl Fn(ArchetypeId) -> bool,
+        moved: &mut Vec<Entity>,
+    ) -> Vec<ArchetypeId> {
+        let id = archetype.id();
+        let (_, previous) = self.archetype_roots.remove(&id).unwrap_or_default();
+        let current: Vec<Entity> = archetype.entities().iter().map(|e| e.entity()).collect();
+        let mut next = Vec::new();
+        for &root in &current {
+            match self.root_archetypes.insert(root, id) {
+                None => {
+                    self.insert_root(root);
+                    moved.push(root);
+                }
+                Some(left) if left != id => next.push(left),
+                Some(_) => {}
+            }
+        }
+        let current_set: HashSet<Entity> = current.iter().copied().collect();
+        for root in previous {
+            // the roots already found in another archetype are kept
+            if current_set.contains(&root) || self.root_archetypes.get(&root) != Some(&id) {
+                continue;
+            }
+            match entities.get(root) {
+                Some(location) if has_roots(location.archetype_id) => {
+                    next.push(location.archetype_id);
+                }
+                _ => {
+                    self.root_archetypes.remove(&root);
+                    self.remove_root(root);
+                    moved.push(root);
+                }
+            }
+        }
+        self.archetype_roots.insert(id, (archetype.len(), current));
+        next
+    }
+}
+
+/// Update the roots and the changes of the hierarchy.
+///
+/// Spawning or despawning a root changes no component of the hierarchy. Instead of collecting
+/// all the roots every frame, only the archetypes whose length changed are scanned again, with
+/// the archetypes the roots entered or left: the archetypes of the entities that lost a
+/// component, and those of the roots found in another archetype.
+#[allow(clippy::too_many_arguments)]
+pub fn update_hierarchy_tree(
+    mut tree: ResMut<HierarchyTree>,
+    archetypes: &Archetypes,
+    entities: &Entities,
+    components: &Components,
+    removed: &RemovedComponentEvents,
+    mut removal_readers: Local<HashMap<ComponentId, ManualEventReader<RemovedComponentEntity>>>,
+    added_parents: Query<Entity, (Added<Parent>, Without<InspectorEntity>)>,
+    changed_children: Query<Entity, (Changed<Children>, Without<InspectorEntity>)>,
+    changed_names: Query<Entity, (Changed<Name>, Without<InspectorEntity>)>,
+    mut removed_parents: RemovedComponents<Parent>,
+    mut removed_children: RemovedComponents<Children>,
+) {
+    let mut moved: Vec<Entity> = added_parents
+        .iter()
+        .chain(&changed_children)
+        .chain(removed_parents.iter())
+        .chain(removed_children.iter())
+        .collect();
+
+    // the roots are the entities of the archetypes without a parent, outside of the editor
+    let excluded = [
+        components.component_id::<Parent>(),
+        components.component_id::<InspectorEntity>(),
+    ];
+    let has_roots = |id: ArchetypeId| {
+        archetypes
+            .get(id)
+            .is_some_and(|archetype| excluded.iter().flatten().all(|&c| !archetype.contains(c)))
+    };
+
+    // the empty archetype is always scanned, its entities are despawned without removal events
+    let mut dirty = vec![ArchetypeId::EMPTY];
+    dirty.extend(
+        archetypes
+            .iter()
+            .filter(|archetype| has_roots(archetype.id()))
+            .filter(|archetype| {
+                let scanned = tree.archetype_roots.get(&archetype.id());
+                scanned.is_none_or(|&(len, _)| len != archetype.len())
+            })
+            .map(|archetype| archetype.id()),
+    );
+    for entity in added_parents.iter() {
+        dirty.extend(tree.root_archetypes.get(&entity).copied());
+    }
+    for info in components.iter() {
+        let Some(events) = removed.get(info.id()) else {
+            continue;
+        };
+        let reader = removal_readers.entry(info.id()).or_default();
+        for event in reader.iter(events) {
+            let entity: Entity = event.clone().into();
+            dirty.extend(tree.root_archetypes.get(&entity).copied());
+            dirty.extend(entities.get(entity).map(|location| location.archetype_id));
+        }
+    }
+
+    let mut scanned = HashSet::new();
+    while let Some(id) = dirty.pop() {
+        if !scanned.insert(id) || !has_roots(id) {
+            continue;
+        }
+        let archetype = &archetypes[id];
+        let next = tree.scan(archetype, entities, has_roots, &mut moved);
+        dirty.extend(next);
+    }
+
+    let renamed: Vec<Entity> = changed_names.iter().collect();
+    if moved.is_empty() && renamed.is_empty() {
+        return;
+    }
+    tree.moved = moved;
+    tree.renamed = renamed;
+    tree.generation += 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.init_resource::<HierarchyTree>()
+            .add_systems(Update, update_hierarchy_tree);
+        app
+    }
+
+    fn roots(app: &App) -> Vec<Entity> {
+        app.world.resource::<HierarchyTree>().roots().to_vec()
+    }
+
+    #[test]
+    fn roots_follow_spawns_despawns_and_parents() {
+        let mut app = app();
+        let a = app.world.spawn(Name::new("a")).id();
+        let b = app.world.spawn_empty().id();
+        app.world.spawn(InspectorEntity);
+        app.update();
+        assert_eq!(roots(&app), [a, b]);
+
+        // the spawn of c hides the despawn of a in the length of their archetype
+        app.world.despawn(a);
+        let c = app.world.spawn(Name::new("c")).id();
+        app.update();
+        assert_eq!(roots(&app), [b, c]);
+
+        app.world.entity_mut(c).set_parent(b);
+        app.update();
+        assert_eq!(roots(&app), [b]);
+        assert!(app.world.resource::<HierarchyTree>().moved().contains(&c));
+
+        app.world.entity_mut(c).remove_parent();
+        app.update();
+        assert_eq!(roots(&app), [b, c]);
+
+        // b leaves the empty archetype without removal events
+        app.world.entity_mut(b).insert(Name::new("b"));
+        app.update();
+        assert_eq!(roots(&app), [b, c]);
+
+        app.world.despawn(b);
+        app.update();
+        assert_eq!(roots(&app), [c]);
+        assert!(app.world.resource::<HierarchyTree>().moved().contains(&b));
+    }
+}
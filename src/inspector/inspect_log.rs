@@ -1,85 +1,495 @@
 #![allow(dead_code)]
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
+use bevy::core::FrameCount;
 use bevy::prelude::*;
+use bevy::utils::tracing::{self, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
 
+/// Number of entries kept, the oldest ones are dropped first.
+pub const MAX_LOGS: usize = 10_000;
+
+/// Records shown in the Console, a [`log::Log`] installed with the platform logger.
+///
+/// Bevy logs with `tracing` instead, its events are stored by a [`LogLayer`] that the inspector
+/// adds to the global subscriber.
+///
+/// A game installing its own logger can forward the records to a `Logs` instead, inserted
+/// before the inspector plugin:
+///
+/// ```ignore
+/// let logs = Logs::default();
+/// MyLogger::new().with(logs.clone()).install();
+/// app.insert_resource(logs).add_plugins(InspectorPlugin);
+/// ```
 #[derive(Clone, Default, Resource)]
 pub struct Logs {
-    logs: Arc<RwLock<HashMap<LogItem, u64>>>,
+    buffer: Arc<RwLock<LogBuffer>>,
+    /// Frame being run, updated by [`update_log_frame`].
+    frame: Arc<AtomicU32>,
+    filter: Arc<RwLock<LogFilter>>,
+    /// Every target seen so far, including the ones of the records which weren't stored.
+    targets: Arc<RwLock<BTreeSet<String>>>,
+}
+
+#[derive(Default)]
+struct LogBuffer {
+    /// Their sequences follow each other.
+    items: VecDeque<LogItem>,
+    next_sequence: u64,
+    /// Number of stored items of each level, indexed by `level as usize - 1`.
+    level_counts: [usize; 5],
 }
 
-#[derive(Clone, Hash, PartialEq, Eq)]
+#[derive(Clone)]
 pub struct LogItem {
+    /// Increases with every record, even after the logs were cleared.
+    pub sequence: u64,
+    /// Wall-clock time of the record, since the unix epoch.
+    pub time: Duration,
+    pub frame: u32,
+    pub thread: Option<String>,
     pub level_log: log::Level,
+    pub target: String,
     pub module: String,
     pub file: String,
     pub line: String,
     pub details: String,
+    /// Structured key/values of the record, e.g. `log::info!(health = 3; "hit")`.
+    pub fields: Vec<(String, String)>,
 }
 
-impl<'a> From<&log::Record<'a>> for LogItem {
-    fn from(v: &log::Record) -> Self {
+impl LogItem {
+    fn new(record: &log::Record, sequence: u64, frame: u32) -> Self {
+        Self {
+            sequence,
+            time: wall_clock(),
+            frame,
+            thread: std::thread::current().name().map(str::to_string),
+            level_log: record.level(),
+            target: record.target().to_string(),
+            module: record.module_path().unwrap_or_default().to_string(),
+            file: record.file().unwrap_or_default().to_string(),
+            line: record.line().unwrap_or_default().to_string(),
+            details: record.args().to_string(),
+            fields: record_fields(record),
+        }
+    }
+
+    fn from_event(event: &tracing::Event, sequence: u64, frame: u32) -> Self {
+        let metadata = event.metadata();
+        let mut fields = EventFields::default();
+        event.record(&mut fields);
         Self {
-            level_log: v.level(),
-            module: v.module_path().unwrap_or_default().to_string(),
-            file: v.file().unwrap_or_default().to_string(),
-            line: v.line().unwrap_or_default().to_string(),
-            details: v.args().to_string(),
+            sequence,
+            time: wall_clock(),
+            frame,
+            thread: std::thread::current().name().map(str::to_string),
+            level_log: log_level(metadata.level()),
+            target: metadata.target().to_string(),
+            module: metadata.module_path().unwrap_or_default().to_string(),
+            file: metadata.file().unwrap_or_default().to_string(),
+            line: metadata.line().unwrap_or_default().to_string(),
+            details: fields.message,
+            fields: fields.fields,
         }
     }
 }
 
+fn log_level(level: &tracing::Level) -> log::Level {
+    match *level {
+        tracing::Level::ERROR => log::Level::Error,
+        tracing::Level::WARN => log::Level::Warn,
+        tracing::Level::INFO => log::Level::Info,
+        tracing::Level::DEBUG => log::Level::Debug,
+        _ => log::Level::Trace,
+    }
+}
+
+/// The message and the other fields of a `tracing` event.
+#[derive(Default)]
+struct EventFields {
+    message: String,
+    fields: Vec<(String, String)>,
+}
+
+impl EventFields {
+    fn record(&mut self, field: &tracing::field::Field, value: String) {
+        match field.name() {
+            "message" => self.message = value,
+            name => self.fields.push((name.to_string(), value)),
+        }
+    }
+}
+
+impl tracing::field::Visit for EventFields {
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        self.record(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+        self.record(field, format!("{value:?}"));
+    }
+}
+
+fn record_fields(record: &log::Record) -> Vec<(String, String)> {
+    struct Fields(Vec<(String, String)>);
+
+    impl<'kvs> log::kv::VisitSource<'kvs> for Fields {
+        fn visit_pair(
+            &mut self,
+            key: log::kv::Key<'kvs>,
+            value: log::kv::Value<'kvs>,
+        ) -> Result<(), log::kv::Error> {
+            self.0.push((key.to_string(), value.to_string()));
+            Ok(())
+        }
+    }
+
+    let mut fields = Fields(Vec::new());
+    // `Fields` never fails
+    let _ = record.key_values().visit(&mut fields);
+    fields.0
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn wall_clock() -> Duration {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn wall_clock() -> Duration {
+    // `SystemTime::now` panics on wasm
+    Duration::from_secs_f64(js_sys::Date::now() / 1000.0)
+}
+
 impl Logs {
     pub fn clear(&self) {
-        let mut logs = self.logs.write().unwrap();
-        logs.clear();
-        drop(logs);
+        let mut buffer = self.buffer.write().unwrap();
+        buffer.items.clear();
+        buffer.level_counts = [0; 5];
+        drop(buffer);
     }
 
     pub fn len(&self) -> usize {
-        let logs = self.logs.read().unwrap();
-        let len = logs.len();
-        drop(logs);
+        let buffer = self.buffer.read().unwrap();
+        let len = buffer.items.len();
+        drop(buffer);
         len
     }
 
-    pub fn get_logs(&self) -> HashMap<LogItem, u64> {
-        let logs = self.logs.read().unwrap();
-        let v = logs.clone();
-        drop(logs);
-        v
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of stored entries of this level.
+    pub fn count(&self, level: log::Level) -> usize {
+        self.buffer.read().unwrap().level_counts[level as usize - 1]
+    }
+
+    /// Sequence of the oldest stored entry, the sequence of the next record if there is none.
+    pub fn first_sequence(&self) -> u64 {
+        let buffer = self.buffer.read().unwrap();
+        buffer.next_sequence - buffer.items.len() as u64
+    }
+
+    /// Copy of the stored entry with this sequence.
+    pub fn get(&self, sequence: u64) -> Option<LogItem> {
+        let buffer = self.buffer.read().unwrap();
+        let first = buffer.next_sequence - buffer.items.len() as u64;
+        let index = sequence.checked_sub(first)?;
+        buffer.items.get(index as usize).cloned()
+    }
+
+    /// Run `f` on the stored entries from `sequence` to the newest one, returns the sequence
+    /// following the newest one.
+    ///
+    /// The logs are locked meanwhile, `f` must not log.
+    pub fn for_each_since(&self, sequence: u64, f: impl FnMut(&LogItem)) -> u64 {
+        let buffer = self.buffer.read().unwrap();
+        let first = buffer.next_sequence - buffer.items.len() as u64;
+        let skipped = sequence.saturating_sub(first) as usize;
+        buffer.items.iter().skip(skipped).for_each(f);
+        buffer.next_sequence
+    }
+
+    pub fn filter(&self) -> LogFilter {
+        self.filter.read().unwrap().clone()
+    }
+
+    /// Only the records matching the filter are stored and shown, plus the warnings and errors of
+    /// every target so that they are still there once their target is enabled.
+    pub fn set_filter(&self, filter: LogFilter) {
+        *self.filter.write().unwrap() = filter;
     }
+
+    pub fn targets(&self) -> BTreeSet<String> {
+        self.targets.read().unwrap().clone()
+    }
+
+    /// Whether a record is stored, checked before formatting it: the verbose records of the
+    /// dependencies would push the ones of the game out of the storage in a few frames.
+    fn keeps(&self, target: &str, level: log::Level) -> bool {
+        if !self.targets.read().unwrap().contains(target) {
+            self.targets.write().unwrap().insert(target.to_string());
+        }
+        level <= log::Level::Warn || self.filter.read().unwrap().enabled(target, level)
+    }
+
+    /// Store the entry built by `item` from its sequence and frame.
+    fn push(&self, item: impl FnOnce(u64, u32) -> LogItem) {
+        let frame = self.frame.load(Ordering::Relaxed);
+        let mut buffer = self.buffer.write().unwrap();
+        let item = item(buffer.next_sequence, frame);
+        buffer.next_sequence += 1;
+        if buffer.items.len() >= MAX_LOGS {
+            if let Some(oldest) = buffer.items.pop_front() {
+                buffer.level_counts[oldest.level_log as usize - 1] -= 1;
+            }
+        }
+        buffer.level_counts[item.level_log as usize - 1] += 1;
+        buffer.items.push_back(item);
+        drop(buffer);
+    }
+}
+
+/// Level of the records shown for each target, like the `RUST_LOG` directives of `env_logger`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct LogFilter {
+    /// Level of the targets without a directive.
+    pub default: log::LevelFilter,
+    directives: BTreeMap<String, log::LevelFilter>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        LogFilter::game_only()
+    }
+}
+
+impl LogFilter {
+    /// Every record of the game crate, nothing from its dependencies.
+    pub fn game_only() -> Self {
+        let mut filter = LogFilter {
+            default: log::LevelFilter::Off,
+            directives: BTreeMap::new(),
+        };
+        filter.set_directive(game_crate(), Some(log::LevelFilter::Trace));
+        filter
+    }
+
+    /// Parse comma separated directives: `level` for the default and `target=level` for a
+    /// target and its submodules, e.g. `warn,game=trace,wgpu=error`.
+    pub fn parse(directives: &str) -> Result<Self, String> {
+        let mut filter = LogFilter {
+            default: log::LevelFilter::Off,
+            directives: BTreeMap::new(),
+        };
+        for directive in directives.split(',').map(str::trim) {
+            match directive.split_once('=') {
+                _ if directive.is_empty() => {}
+                Some((target, level)) => {
+                    let level = level
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("invalid level in `{directive}`"))?;
+                    filter.set_directive(target.trim(), Some(level));
+                }
+                // a lone word is a level, or a target enabled at every level
+                None => match directive.parse() {
+                    Ok(level) => filter.default = level,
+                    Err(_) => filter.set_directive(directive, Some(log::LevelFilter::Trace)),
+                },
+            }
+        }
+        Ok(filter)
+    }
+
+    /// The directive of this exact target, `None` if it inherits the one of its parent.
+    pub fn directive(&self, target: &str) -> Option<log::LevelFilter> {
+        self.directives.get(target).copied()
+    }
+
+    pub fn set_directive(&mut self, target: &str, level: Option<log::LevelFilter>) {
+        match level {
+            Some(level) => self.directives.insert(target.to_string(), level),
+            None => self.directives.remove(target),
+        };
+    }
+
+    /// Level of the most specific directive matching `target`.
+    pub fn level_for(&self, target: &str) -> log::LevelFilter {
+        self.directives
+            .iter()
+            .filter(|(prefix, _)| {
+                target
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    pub fn enabled(&self, target: &str, level: log::Level) -> bool {
+        level <= self.level_for(target)
+    }
+}
+
+impl fmt::Display for LogFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.default.as_str().to_lowercase())?;
+        for (target, level) in &self.directives {
+            write!(f, ",{target}={}", level.as_str().to_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+/// Name of the game crate, as it appears in the module paths.
+pub fn game_crate() -> &'static str {
+    module_path!().split("::").next().unwrap_or_default()
 }
 
 impl log::Log for Logs {
-    fn enabled(&self, _metadata: &log::Metadata) -> bool {
-        true
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.keeps(metadata.target(), metadata.level())
     }
 
     fn log(&self, record: &log::Record) {
         if !self.enabled(record.metadata()) {
             return;
         }
-        if !record
-            .module_path()
-            .is_some_and(|m| m.starts_with(env!("CARGO_PKG_NAME")))
-        {
-            return;
-        }
-        let mut logs = self.logs.write().unwrap();
-        let item = LogItem::from(record);
-        (*logs)
-            .entry(item)
-            .and_modify(|c| {
-                if *c <= 99 {
-                    *c += 1;
-                }
-            })
-            .or_insert(0);
-        drop(logs);
+        self.push(|sequence, frame| LogItem::new(record, sequence, frame));
     }
 
     fn flush(&self) {}
 }
+
+/// Layer of the tracing subscriber storing the events in the [`Logs`].
+pub struct LogLayer(pub Logs);
+
+impl<S: Subscriber> Layer<S> for LogLayer {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if self.0.keeps(metadata.target(), log_level(metadata.level())) {
+            self.0
+                .push(|sequence, frame| LogItem::from_event(event, sequence, frame));
+        }
+    }
+}
+
+pub fn update_log_frame(logs: Res<Logs>, frame_count: Res<FrameCount>) {
+    logs.frame.store(frame_count.0, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn tracing_events_are_stored_with_their_fields() {
+        let logs = Logs::default();
+        logs.set_filter(LogFilter::parse("off").unwrap());
+        let subscriber = tracing_subscriber::registry().with(LogLayer(logs.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(target: "wgpu::device", health = 3, "hit");
+        });
+
+        // the warnings are stored even though the filter doesn't show them
+        let item = logs.get(logs.first_sequence()).unwrap();
+        assert_eq!(item.level_log, log::Level::Warn);
+        assert_eq!(item.target, "wgpu::device");
+        assert_eq!(item.details, "hit");
+        assert_eq!(item.fields, [("health".to_string(), "3".to_string())]);
+        assert!(logs.targets().contains("wgpu::device"));
+    }
+
+    fn log(logs: &Logs, target: &str, level: log::Level, message: &str) {
+        log::Log::log(
+            logs,
+            &log::Record::builder()
+                .target(target)
+                .level(level)
+                .args(format_args!("{message}"))
+                .build(),
+        );
+    }
+
+    #[test]
+    fn verbose_dependencies_dont_push_out_the_game_logs() {
+        let logs = Logs::default();
+        log(&logs, game_crate(), log::Level::Debug, "spawned");
+        for _ in 0..MAX_LOGS * 2 {
+            log(&logs, "wgpu_core::device", log::Level::Trace, "flood");
+        }
+        log(&logs, "wgpu_core::device", log::Level::Warn, "lost device");
+
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs.get(0).unwrap().details, "spawned");
+        assert_eq!(logs.count(log::Level::Trace), 0);
+        assert!(logs.targets().contains("wgpu_core::device"));
+
+        // the records of an enabled target are stored
+        logs.set_filter(LogFilter::parse("wgpu_core=trace").unwrap());
+        log(&logs, "wgpu_core::device", log::Level::Trace, "shown");
+        assert_eq!(logs.count(log::Level::Trace), 1);
+    }
+
+    #[test]
+    fn the_most_specific_directive_wins() {
+        let filter = LogFilter::parse("warn, wgpu=error, wgpu::device=trace,bevy_render").unwrap();
+        assert_eq!(filter.default, log::LevelFilter::Warn);
+        assert_eq!(filter.level_for("naga"), log::LevelFilter::Warn);
+        assert_eq!(filter.level_for("wgpu"), log::LevelFilter::Error);
+        assert_eq!(filter.level_for("wgpu::queue"), log::LevelFilter::Error);
+        assert_eq!(
+            filter.level_for("wgpu::device::lost"),
+            log::LevelFilter::Trace
+        );
+        // a lone target is enabled at every level
+        assert_eq!(filter.level_for("bevy_render"), log::LevelFilter::Trace);
+        // the prefixes only match whole path segments
+        assert_eq!(filter.level_for("wgpu_core"), log::LevelFilter::Warn);
+
+        assert!(filter.enabled("wgpu::queue", log::Level::Error));
+        assert!(!filter.enabled("wgpu::queue", log::Level::Warn));
+    }
+
+    #[test]
+    fn the_default_level_applies_without_directives() {
+        let filter = LogFilter::parse("").unwrap();
+        assert_eq!(filter.default, log::LevelFilter::Off);
+        assert!(!filter.enabled("game", log::Level::Error));
+
+        let filter = LogFilter::parse("info").unwrap();
+        assert!(filter.enabled("anything", log::Level::Info));
+        assert!(!filter.enabled("anything", log::Level::Debug));
+
+        let filter = LogFilter::game_only();
+        assert!(filter.enabled(game_crate(), log::Level::Trace));
+        assert!(!filter.enabled("wgpu", log::Level::Error));
+    }
+
+    #[test]
+    fn invalid_levels_are_rejected() {
+        assert!(LogFilter::parse("wgpu=loud").is_err());
+        assert!(LogFilter::parse("warn,game=").is_err());
+    }
+
+    #[test]
+    fn the_directives_round_trip() {
+        let filter = LogFilter::parse("warn,wgpu=error,game=trace").unwrap();
+        assert_eq!(filter.to_string(), "warn,game=trace,wgpu=error");
+        assert!(LogFilter::parse(&filter.to_string()).unwrap() == filter);
+    }
+}
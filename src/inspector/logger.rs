@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+use bevy::utils::tracing;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::{Layer, Registry};
+
+use super::inspect_log::{game_crate, LogFilter};
+
+/// Logger forwarding every record to several loggers.
+#[derive(Default)]
+pub struct FanOutLogger {
+    loggers: Vec<Box<dyn log::Log>>,
+}
+
+impl FanOutLogger {
+    pub fn with(mut self, logger: impl log::Log + 'static) -> Self {
+        self.loggers.push(Box::new(logger));
+        self
+    }
+
+    /// Install as the global logger.
+    ///
+    /// Only one logger can be installed: if there is already one it is kept and `false` is
+    /// returned, the loggers of `self` won't receive anything. The installed logger should then
+    /// forward its records to a [`Logs`](super::inspect_log::Logs) inserted in the app.
+    pub fn install(self) -> bool {
+        match log::set_boxed_logger(Box::new(self)) {
+            Ok(()) => {
+                log::set_max_level(log::LevelFilter::Trace);
+                true
+            }
+            Err(_) => {
+                log::warn!(
+                    "A logger is already installed, the Console tab only shows the logs it \
+                     forwards to a `Logs` resource inserted before the inspector"
+                );
+                false
+            }
+        }
+    }
+}
+
+impl log::Log for FanOutLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.loggers.iter().any(|logger| logger.enabled(metadata))
+    }
+
+    fn log(&self, record: &log::Record) {
+        for logger in &self.loggers {
+            if logger.enabled(record.metadata()) {
+                logger.log(record);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        for logger in &self.loggers {
+            logger.flush();
+        }
+    }
+}
+
+/// Logger writing to the output of the platform: stdout and stderr, the browser console or
+/// Android logcat.
+pub struct PlatformLogger {
+    filter: LogFilter,
+    #[cfg(target_os = "android")]
+    android: android_logger::AndroidLogger,
+}
+
+impl Default for PlatformLogger {
+    /// Filtered with `RUST_LOG` when it is set, otherwise the warnings of every target and
+    /// the infos of the game crate are shown.
+    fn default() -> Self {
+        let filter = std::env::var("RUST_LOG")
+            .ok()
+            .and_then(|directives| LogFilter::parse(&directives).ok())
+            .unwrap_or_else(|| {
+                let mut filter = LogFilter::parse("warn").unwrap_or_default();
+                filter.set_directive(game_crate(), Some(log::LevelFilter::Info));
+                filter
+            });
+
+        Self {
+            filter,
+            #[cfg(target_os = "android")]
+            android: android_logger::AndroidLogger::new(
+                android_logger::Config::default().with_max_level(log::LevelFilter::Trace),
+            ),
+        }
+    }
+}
+
+impl log::Log for PlatformLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.filter.enabled(metadata.target(), metadata.level())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn log(&self, record: &log::Record) {
+        use web_sys::console;
+
+        let message = format!("{} {}: {}", record.level(), record.target(), record.args()).into();
+        match record.level() {
+            log::Level::Error => console::error_1(&message),
+            log::Level::Warn => console::warn_1(&message),
+            log::Level::Info => console::info_1(&message),
+            log::Level::Debug | log::Level::Trace => console::debug_1(&message),
+        }
+    }
+
+    #[cfg(target_os = "android")]
+    fn log(&self, record: &log::Record) {
+        log::Log::log(&self.android, record);
+    }
+
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    fn log(&self, record: &log::Record) {
+        let message = format!(
+            "{:<5} {}: {}",
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        if record.level() <= log::Level::Warn {
+            eprintln!("{message}");
+        } else {
+            println!("{message}");
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Layers of the global `tracing` subscriber, added by the plugins when they are built.
+///
+/// Only one subscriber can be installed, so it is installed with every layer by
+/// [`install_subscriber`] once all the plugins are built, whatever their order.
+#[derive(Resource, Default)]
+pub struct SubscriberLayers(Vec<Box<dyn Layer<Registry> + Send + Sync>>);
+
+impl SubscriberLayers {
+    pub fn add(app: &mut App, layer: impl Layer<Registry> + Send + Sync + 'static) {
+        let mut layers = app.world.get_resource_or_insert_with(Self::default);
+        layers.0.push(Box::new(layer));
+    }
+}
+
+/// Install the subscriber with the [`SubscriberLayers`], from the `finish` of the plugins adding
+/// them. The first call installs it.
+pub fn install_subscriber(app: &mut App) {
+    let Some(SubscriberLayers(layers)) = app.world.remove_resource::<SubscriberLayers>() else {
+        return;
+    };
+    let subscriber = tracing_subscriber::registry().with(layers);
+    if tracing::subscriber::set_global_default(subscriber).is_err() {
+        log::warn!(
+            "A tracing subscriber is already installed, the Profiler won't record and the \
+             Console won't show the logs of Bevy"
+        );
+    }
+}
@@ -0,0 +1,395 @@
+use bevy::prelude::*;
+use bevy::render::camera::CameraProjection;
+use bevy::render::primitives::Aabb;
+use bevy_egui::egui;
+use bevy_inspector_egui::bevy_inspector::hierarchy::SelectedEntities;
+
+use super::default_scene::{InspectorEntity, MainSceneCamera};
+
+/// Frustums are cut at this distance, perspective projections would go on for a long time.
+const MAX_FRUSTUM_DISTANCE: f32 = 10.0;
+
+/// Number of segments of the circles.
+const CIRCLE_SEGMENTS: usize = 32;
+const STROKE_WIDTH: f32 = 1.5;
+
+const SELECTION_COLOR: Color = Color::ORANGE;
+const AABB_COLOR: Color = Color::GRAY;
+const LIGHT_COLOR: Color = Color::YELLOW;
+const CAMERA_COLOR: Color = Color::WHITE;
+
+/// What is drawn over the entities in the Scene view.
+#[derive(Clone, Copy)]
+pub struct OverlaySettings {
+    pub selection: bool,
+    pub aabbs: bool,
+    pub lights: bool,
+    pub cameras: bool,
+}
+
+impl Default for OverlaySettings {
+    fn default() -> Self {
+        Self {
+            selection: true,
+            aabbs: false,
+            lights: false,
+            cameras: false,
+        }
+    }
+}
+
+/// Draw the overlays over the Scene view image at `viewport`.
+///
+/// They are painted with egui rather than [`Gizmos`]: the gizmos share a single render layer
+/// set by the game in [`GizmoConfig`], so they would show up in the Game view as well.
+pub fn draw_overlays(
+    ui: &egui::Ui,
+    viewport: egui::Rect,
+    world: &mut World,
+    settings: OverlaySettings,
+    selected_entities: &SelectedEntities,
+) {
+    let Ok((camera_transform, projection)) = world
+        .query_filtered::<(&GlobalTransform, &Projection), With<MainSceneCamera>>()
+        .get_single(world)
+    else {
+        return;
+    };
+    let painter = OverlayPainter {
+        painter: ui.painter_at(viewport),
+        viewport,
+        world_to_clip: projection.get_projection_matrix()
+            * camera_transform.compute_matrix().inverse(),
+    };
+
+    if settings.aabbs {
+        let mut bounds =
+            world.query_filtered::<(&Aabb, &GlobalTransform), Without<InspectorEntity>>();
+        for (aabb, transform) in bounds.iter(world) {
+            painter.cuboid(aabb_matrix(aabb, transform), AABB_COLOR);
+        }
+    }
+
+    if settings.lights {
+        for (light, transform) in world.query::<(&PointLight, &GlobalTransform)>().iter(world) {
+            painter.sphere(transform.translation(), light.range, LIGHT_COLOR);
+        }
+        for (light, transform) in world.query::<(&SpotLight, &GlobalTransform)>().iter(world) {
+            let tip = transform.translation();
+            let forward = transform.forward();
+            let radius = light.range * light.outer_angle.tan();
+            let base = tip + forward * light.range;
+            painter.circle(base, forward, radius, LIGHT_COLOR);
+            for side in [
+                transform.up(),
+                transform.down(),
+                transform.left(),
+                transform.right(),
+            ] {
+                painter.line(tip, base + side * radius, LIGHT_COLOR);
+            }
+        }
+        let mut directional_lights =
+            world.query_filtered::<&GlobalTransform, With<DirectionalLight>>();
+        for transform in directional_lights.iter(world) {
+            painter.line(
+                transform.translation(),
+                transform.translation() + transform.forward(),
+                LIGHT_COLOR,
+            );
+        }
+    }
+
+    if settings.cameras {
+        let mut cameras = world.query_filtered::<
+            (&Projection, &GlobalTransform),
+            (With<Camera>, Without<InspectorEntity>),
+        >();
+        for (projection, transform) in cameras.iter(world) {
+            let (near, far) = frustum_corners(projection, MAX_FRUSTUM_DISTANCE);
+            let near = near.map(|corner| transform.transform_point(corner));
+            let far = far.map(|corner| transform.transform_point(corner));
+            painter.linestrip(near.into_iter().chain([near[0]]), CAMERA_COLOR);
+            painter.linestrip(far.into_iter().chain([far[0]]), CAMERA_COLOR);
+            for (near, far) in near.into_iter().zip(far) {
+                painter.line(near, far, CAMERA_COLOR);
+            }
+        }
+    }
+
+    if settings.selection {
+        let mut bounds = world.query::<(Option<&Aabb>, &GlobalTransform)>();
+        for entity in selected_entities.iter() {
+            match bounds.get(world, entity) {
+                Ok((Some(aabb), transform)) => {
+                    painter.cuboid(aabb_matrix(aabb, transform), SELECTION_COLOR);
+                }
+                // entities without bounds (empty, lights...) are shown by their axes
+                Ok((None, transform)) => {
+                    let origin = transform.translation();
+                    let (_, rotation, _) = transform.to_scale_rotation_translation();
+                    for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+                        painter.line(origin, origin + rotation * axis * 0.5, SELECTION_COLOR);
+                    }
+                }
+                Err(_) => {}
+            }
+        }
+    }
+}
+
+/// Paints lines given in world space over the Scene view image.
+struct OverlayPainter {
+    painter: egui::Painter,
+    viewport: egui::Rect,
+    world_to_clip: Mat4,
+}
+
+impl OverlayPainter {
+    fn line(&self, start: Vec3, end: Vec3, color: Color) {
+        let Some(segment) = self.segment(start, end) else {
+            return;
+        };
+        let [r, g, b, a] = color.as_rgba_u8();
+        self.painter.line_segment(
+            segment,
+            egui::Stroke::new(
+                STROKE_WIDTH,
+                egui::Color32::from_rgba_unmultiplied(r, g, b, a),
+            ),
+        );
+    }
+
+    fn linestrip(&self, points: impl IntoIterator<Item = Vec3>, color: Color) {
+        let mut points = points.into_iter();
+        let Some(mut start) = points.next() else {
+            return;
+        };
+        for end in points {
+            self.line(start, end, color);
+            start = end;
+        }
+    }
+
+    /// Edges of the unit cube transformed by `matrix`.
+    fn cuboid(&self, matrix: Mat4, color: Color) {
+        let corner = |x: f32, y: f32, z: f32| matrix.transform_point3(Vec3::new(x, y, z) * 0.5);
+        let bottom = [
+            corner(-1., -1., -1.),
+            corner(1., -1., -1.),
+            corner(1., -1., 1.),
+            corner(-1., -1., 1.),
+        ];
+        let top = bottom.map(|point| point + matrix.transform_vector3(Vec3::Y));
+        self.linestrip(bottom.into_iter().chain([bottom[0]]), color);
+        self.linestrip(top.into_iter().chain([top[0]]), color);
+        for (bottom, top) in bottom.into_iter().zip(top) {
+            self.line(bottom, top, color);
+        }
+    }
+
+    fn circle(&self, center: Vec3, normal: Vec3, radius: f32, color: Color) {
+        let rotation = Quat::from_rotation_arc(Vec3::Z, normal.normalize());
+        let points = (0..=CIRCLE_SEGMENTS).map(|i| {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+            center + rotation * Vec3::new(angle.cos(), angle.sin(), 0.0) * radius
+        });
+        self.linestrip(points, color);
+    }
+
+    fn sphere(&self, center: Vec3, radius: f32, color: Color) {
+        for normal in [Vec3::X, Vec3::Y, Vec3::Z] {
+            self.circle(center, normal, radius, color);
+        }
+    }
+
+    /// Ends in the viewport of a segment in world space, cut at the near plane, `None` if it is
+    /// behind the camera.
+    fn segment(&self, start: Vec3, end: Vec3) -> Option<[egui::Pos2; 2]> {
+        let mut start = self.world_to_clip * start.extend(1.0);
+        let mut end = self.world_to_clip * end.extend(1.0);
+        // the depth is reversed, points are in front of the near plane while z <= w
+        let start_depth = start.w - start.z;
+        let end_depth = end.w - end.z;
+        if start_depth < 0.0 && end_depth < 0.0 {
+            return None;
+        }
+        if start_depth < 0.0 {
+            start = start.lerp(end, start_depth / (start_depth - end_depth));
+        } else if end_depth < 0.0 {
+            end = end.lerp(start, end_depth / (end_depth - start_depth));
+        }
+        Some([self.to_screen(start), self.to_screen(end)])
+    }
+
+    /// Position in the viewport of a point in clip space.
+    fn to_screen(&self, clip: Vec4) -> egui::Pos2 {
+        let ndc = clip.truncate().truncate() / clip.w;
+        let uv = egui::vec2(ndc.x + 1.0, 1.0 - ndc.y) / 2.0;
+        self.viewport.min + uv * self.viewport.size()
+    }
+}
+
+/// Matrix of the unit cube scaled to fit `aabb`.
+fn aabb_matrix(aabb: &Aabb, transform: &GlobalTransform) -> Mat4 {
+    let size = Vec3::from(aabb.half_extents) * 2.0;
+    let center = Vec3::from(aabb.center);
+    transform.compute_matrix() * Mat4::from_scale_rotation_translation(size, Quat::IDENTITY, center)
+}
+
+/// Corners of the near and far planes of a camera frustum cut at `max_distance`, in the camera
+/// space and clockwise from the top left.
+pub fn frustum_corners(projection: &Projection, max_distance: f32) -> ([Vec3; 4], [Vec3; 4]) {
+    match projection {
+        Projection::Perspective(projection) => {
+            let far = projection.far.min(max_distance);
+            let half_size = |distance: f32| {
+                let half_height = distance * (projection.fov / 2.0).tan();
+                Vec2::new(half_height * projection.aspect_ratio, half_height)
+            };
+            (
+                rect_corners(
+                    -half_size(projection.near),
+                    half_size(projection.near),
+                    projection.near,
+                ),
+                rect_corners(-half_size(far), half_size(far), far),
+            )
+        }
+        Projection::Orthographic(projection) => {
+            let far = projection.far.min(projection.near + max_distance);
+            let area = projection.area;
+            (
+                rect_corners(area.min, area.max, projection.near),
+                rect_corners(area.min, area.max, far),
+            )
+        }
+    }
+}
+
+/// Corners of the rectangle going from `min` to `max` at `distance` in front of the camera.
+fn rect_corners(min: Vec2, max: Vec2, distance: f32) -> [Vec3; 4] {
+    // cameras look towards -z
+    let z = -distance;
+    [
+        Vec3::new(min.x, max.y, z),
+        Vec3::new(max.x, max.y, z),
+        Vec3::new(max.x, min.y, z),
+        Vec3::new(min.x, min.y, z),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn perspective() -> PerspectiveProjection {
+        PerspectiveProjection {
+            fov: std::f32::consts::FRAC_PI_2,
+            aspect_ratio: 2.0,
+            near: 1.0,
+            far: 1000.0,
+        }
+    }
+
+    fn assert_corners(corners: [Vec3; 4], expected: [[f32; 3]; 4]) {
+        for (corner, expected) in corners.into_iter().zip(expected) {
+            let expected = Vec3::from(expected);
+            assert!(corner.abs_diff_eq(expected, 1e-4), "{corner} != {expected}");
+        }
+    }
+
+    #[test]
+    fn perspective_frustum_is_cut_at_the_max_distance() {
+        let (near, far) = frustum_corners(&Projection::Perspective(perspective()), 10.0);
+        assert_corners(
+            near,
+            [
+                [-2., 1., -1.],
+                [2., 1., -1.],
+                [2., -1., -1.],
+                [-2., -1., -1.],
+            ],
+        );
+        assert_corners(
+            far,
+            [
+                [-20., 10., -10.],
+                [20., 10., -10.],
+                [20., -10., -10.],
+                [-20., -10., -10.],
+            ],
+        );
+    }
+
+    #[test]
+    fn orthographic_frustum_is_cut_after_the_near_plane() {
+        let mut projection = OrthographicProjection {
+            near: 2.0,
+            far: 100.0,
+            ..default()
+        };
+        projection.area = Rect::new(-4.0, -3.0, 4.0, 3.0);
+        let (near, far) = frustum_corners(&Projection::Orthographic(projection), 10.0);
+        assert_corners(
+            near,
+            [
+                [-4., 3., -2.],
+                [4., 3., -2.],
+                [4., -3., -2.],
+                [-4., -3., -2.],
+            ],
+        );
+        assert_corners(
+            far,
+            [
+                [-4., 3., -12.],
+                [4., 3., -12.],
+                [4., -3., -12.],
+                [-4., -3., -12.],
+            ],
+        );
+    }
+
+    /// Painter of a 200x100 viewport seen from the origin, looking towards -z.
+    fn painter() -> OverlayPainter {
+        let viewport = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(200.0, 100.0));
+        OverlayPainter {
+            painter: egui::Painter::new(
+                egui::Context::default(),
+                egui::LayerId::background(),
+                viewport,
+            ),
+            viewport,
+            world_to_clip: perspective().get_projection_matrix(),
+        }
+    }
+
+    fn assert_pos(pos: egui::Pos2, expected: egui::Pos2) {
+        assert!((pos - expected).length() < 1e-3, "{pos:?} != {expected:?}");
+    }
+
+    #[test]
+    fn segments_are_cut_at_the_near_plane() {
+        let painter = painter();
+        let [start, end] = painter
+            .segment(Vec3::new(-2.0, 0.0, -2.0), Vec3::new(0.0, 1.0, -2.0))
+            .unwrap();
+        assert_pos(start, egui::pos2(50.0, 50.0));
+        assert_pos(end, egui::pos2(100.0, 25.0));
+
+        // the part behind the near plane ends where the segment crosses it
+        let crossing = painter.segment(Vec3::new(1.0, 0.0, 3.0), Vec3::new(1.0, 0.0, -5.0));
+        let [start, end] = crossing.unwrap();
+        assert_pos(start, egui::pos2(150.0, 50.0));
+        assert_pos(end, egui::pos2(110.0, 50.0));
+        let [start, end] = painter
+            .segment(Vec3::new(1.0, 0.0, -5.0), Vec3::new(1.0, 0.0, 3.0))
+            .unwrap();
+        assert_pos(start, egui::pos2(110.0, 50.0));
+        assert_pos(end, egui::pos2(150.0, 50.0));
+
+        let behind = painter.segment(Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -0.5));
+        assert!(behind.is_none());
+    }
+}
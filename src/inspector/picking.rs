@@ -0,0 +1,240 @@
+use bevy::math::Ray;
+use bevy::prelude::*;
+use bevy::render::mesh::{PrimitiveTopology, VertexAttributeValues};
+use bevy::render::primitives::Aabb;
+
+use super::default_scene::InspectorEntity;
+
+/// Ray starting on the near plane of a camera and going through `ndc`, a point in normalized
+/// device coordinates (`-1..=1`, y up).
+pub fn viewport_ray(
+    camera_transform: &GlobalTransform,
+    projection: Mat4,
+    ndc: Vec2,
+) -> Option<Ray> {
+    let ndc_to_world = camera_transform.compute_matrix() * projection.inverse();
+    // bevy uses a reversed infinite z: the near plane is at 1 and the far plane at 0
+    let near = ndc_to_world.project_point3(ndc.extend(1.0));
+    let far = ndc_to_world.project_point3(ndc.extend(f32::EPSILON));
+    let direction = (far - near).try_normalize()?;
+    (!near.is_nan()).then_some(Ray {
+        origin: near,
+        direction,
+    })
+}
+
+/// Distance along `ray` to the box going from `min` to `max`, `0` if the ray starts inside it.
+pub fn ray_aabb(ray: Ray, min: Vec3, max: Vec3) -> Option<f32> {
+    let inv_direction = ray.direction.recip();
+    let t1 = (min - ray.origin) * inv_direction;
+    let t2 = (max - ray.origin) * inv_direction;
+    let t_enter = t1.min(t2).max_element().max(0.0);
+    let t_exit = t1.max(t2).min_element();
+    (t_exit >= t_enter).then_some(t_enter)
+}
+
+/// Distance along `ray` to the triangle `[a, b, c]` (Möller–Trumbore), both faces are hit.
+pub fn ray_triangle(ray: Ray, [a, b, c]: [Vec3; 3]) -> Option<f32> {
+    let ab = b - a;
+    let ac = c - a;
+    let p = ray.direction.cross(ac);
+    let det = ab.dot(p);
+    if det.abs() < f32::EPSILON {
+        // the ray is parallel to the triangle
+        return None;
+    }
+    let inv_det = det.recip();
+
+    let ao = ray.origin - a;
+    let u = ao.dot(p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = ao.cross(ab);
+    let v = ray.direction.dot(q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = ac.dot(q) * inv_det;
+    (t >= 0.0).then_some(t)
+}
+
+/// Distance along `ray` to the closest triangle of `mesh`, the ray being in the mesh space.
+///
+/// Only triangle lists are supported, `None` is returned for the other topologies.
+pub fn ray_mesh(ray: Ray, mesh: &Mesh) -> Option<f32> {
+    if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+        return None;
+    }
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return None;
+    };
+
+    let indices: Vec<usize> = match mesh.indices() {
+        Some(indices) => indices.iter().collect(),
+        None => (0..positions.len()).collect(),
+    };
+    indices
+        .chunks(3)
+        .filter_map(|triangle| {
+            let &[a, b, c] = triangle else {
+                return None;
+            };
+            let vertices = [positions.get(a)?, positions.get(b)?, positions.get(c)?];
+            ray_triangle(ray, vertices.map(|&vertex| Vec3::from(vertex)))
+        })
+        .min_by(f32::total_cmp)
+}
+
+/// Closest visible entity hit by `ray`, the entities of the inspector are ignored.
+///
+/// Entities are tested against their [`Aabb`], then against the triangles of their mesh
+/// if `precise` is set.
+pub fn pick_entity(world: &mut World, ray: Ray, precise: bool) -> Option<Entity> {
+    let mut query = world.query_filtered::<(
+        Entity,
+        &Aabb,
+        &GlobalTransform,
+        &ComputedVisibility,
+        Option<&Handle<Mesh>>,
+    ), Without<InspectorEntity>>();
+    let meshes = world.resource::<Assets<Mesh>>();
+
+    query
+        .iter(world)
+        .filter(|(_, _, _, visibility, _)| visibility.is_visible_in_hierarchy())
+        .filter_map(|(entity, aabb, transform, _, mesh)| {
+            // the direction isn't normalized, so distances along the local ray stay in world units
+            let to_local = transform.affine().inverse();
+            let local_ray = Ray {
+                origin: to_local.transform_point3(ray.origin),
+                direction: to_local.transform_vector3(ray.direction),
+            };
+
+            let distance = ray_aabb(local_ray, aabb.min().into(), aabb.max().into())?;
+            let distance = match mesh.and_then(|mesh| meshes.get(mesh)) {
+                Some(mesh) if precise => ray_mesh(local_ray, mesh)?,
+                _ => distance,
+            };
+            Some((entity, distance))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::camera::CameraProjection;
+    use bevy::render::mesh::shape;
+    use bevy::render::view::VisibilityPlugin;
+
+    use super::*;
+
+    const TRIANGLE: [Vec3; 3] = [
+        Vec3::new(-1.0, -1.0, 0.0),
+        Vec3::new(1.0, -1.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+    ];
+
+    fn ray(origin: Vec3, direction: Vec3) -> Ray {
+        Ray { origin, direction }
+    }
+
+    fn assert_near(distance: Option<f32>, expected: f32) {
+        let distance = distance.expect("the ray should hit");
+        assert!(
+            (distance - expected).abs() < 1e-4,
+            "{distance} instead of {expected}"
+        );
+    }
+
+    #[test]
+    fn viewport_ray_goes_through_the_point() {
+        let camera = GlobalTransform::from(Transform::from_xyz(0.0, 0.0, 5.0));
+        let projection = PerspectiveProjection::default().get_projection_matrix();
+
+        let center = viewport_ray(&camera, projection, Vec2::ZERO).unwrap();
+        assert!(center.origin.distance(Vec3::new(0.0, 0.0, 5.0)) < 1.0);
+        assert!(center.direction.distance(Vec3::NEG_Z) < 1e-4);
+
+        let right = viewport_ray(&camera, projection, Vec2::new(1.0, 0.0)).unwrap();
+        assert!(right.direction.x > 0.0 && right.direction.z < 0.0);
+        assert!(right.direction.y.abs() < 1e-4);
+    }
+
+    #[test]
+    fn ray_aabb_hit_and_miss() {
+        let hit = |origin, direction| ray_aabb(ray(origin, direction), Vec3::NEG_ONE, Vec3::ONE);
+        assert_near(hit(Vec3::new(0.0, 0.0, 5.0), Vec3::NEG_Z), 4.0);
+        assert_near(hit(Vec3::ZERO, Vec3::NEG_Z), 0.0);
+        assert_eq!(hit(Vec3::new(3.0, 0.0, 5.0), Vec3::NEG_Z), None);
+        // behind the origin
+        assert_eq!(hit(Vec3::new(0.0, 0.0, 5.0), Vec3::Z), None);
+        // parallel to the faces, beside the box
+        assert_eq!(hit(Vec3::new(0.0, 2.0, 5.0), Vec3::NEG_Z), None);
+    }
+
+    #[test]
+    fn ray_triangle_hit_and_miss() {
+        let hit = |origin, direction| ray_triangle(ray(origin, direction), TRIANGLE);
+        assert_near(hit(Vec3::new(0.0, 0.0, 5.0), Vec3::NEG_Z), 5.0);
+        // the back face is hit too
+        assert_near(hit(Vec3::new(0.0, 0.0, -2.0), Vec3::Z), 2.0);
+        assert_eq!(hit(Vec3::new(0.0, 2.0, 5.0), Vec3::NEG_Z), None);
+        // behind the origin
+        assert_eq!(hit(Vec3::new(0.0, 0.0, 5.0), Vec3::Z), None);
+        // parallel to the triangle
+        assert_eq!(hit(Vec3::new(-5.0, 0.0, 0.0), Vec3::X), None);
+    }
+
+    #[test]
+    fn ray_mesh_hits_the_closest_triangle() {
+        let cube = Mesh::from(shape::Cube { size: 2.0 });
+        let hit = |origin, direction| ray_mesh(ray(origin, direction), &cube);
+        assert_near(hit(Vec3::new(0.0, 0.0, 5.0), Vec3::NEG_Z), 4.0);
+        assert_near(hit(Vec3::new(0.5, 0.5, -5.0), Vec3::Z), 4.0);
+        assert_eq!(hit(Vec3::new(3.0, 0.0, 5.0), Vec3::NEG_Z), None);
+        assert_eq!(hit(Vec3::new(0.0, 0.0, 5.0), Vec3::Z), None);
+
+        let lines = Mesh::new(PrimitiveTopology::LineList);
+        let ray = ray(Vec3::new(0.0, 0.0, 5.0), Vec3::NEG_Z);
+        assert_eq!(ray_mesh(ray, &lines), None);
+    }
+
+    #[test]
+    fn pick_entity_returns_the_nearest() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .add_plugins((TransformPlugin, HierarchyPlugin, VisibilityPlugin))
+            .add_asset::<Mesh>();
+        let mesh = app
+            .world
+            .resource_mut::<Assets<Mesh>>()
+            .add(shape::Cube { size: 2.0 }.into());
+        let mut spawn_cube = |z: f32| {
+            let transform = Transform::from_xyz(0.0, 0.0, z);
+            let bundle = (mesh.clone(), SpatialBundle::from_transform(transform));
+            app.world.spawn(bundle).id()
+        };
+        let far = spawn_cube(-3.0);
+        let near = spawn_cube(0.0);
+        app.update();
+
+        for precise in [false, true] {
+            let front = ray(Vec3::new(0.0, 0.0, 5.0), Vec3::NEG_Z);
+            assert_eq!(pick_entity(&mut app.world, front, precise), Some(near));
+            let back = ray(Vec3::new(0.0, 0.0, -10.0), Vec3::Z);
+            assert_eq!(pick_entity(&mut app.world, back, precise), Some(far));
+            let beside = ray(Vec3::new(5.0, 0.0, 5.0), Vec3::NEG_Z);
+            assert_eq!(pick_entity(&mut app.world, beside, precise), None);
+        }
+
+        app.world.entity_mut(near).insert(Visibility::Hidden);
+        app.update();
+        let front = ray(Vec3::new(0.0, 0.0, 5.0), Vec3::NEG_Z);
+        assert_eq!(pick_entity(&mut app.world, front, true), Some(far));
+    }
+}
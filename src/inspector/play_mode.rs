@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use super::default_scene::InspectorEntity;
+use super::undo::{EntitiesSnapshot, UndoHistory};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlayState {
+    /// The game systems don't run, edits are permanent.
+    #[default]
+    Stopped,
+    Playing,
+    Paused,
+}
+
+/// Editor play mode: the game systems and the virtual [`Time`] only run while playing.
+///
+/// The user world is captured when the game starts playing and restored when it is stopped.
+#[derive(Resource, Default)]
+pub struct PlayMode {
+    state: PlayState,
+    step_requested: bool,
+    /// `true` during the frame run by [`PlayMode::step`].
+    stepping: bool,
+    snapshot: Option<EntitiesSnapshot>,
+}
+
+impl PlayMode {
+    pub fn state(&self) -> PlayState {
+        self.state
+    }
+
+    /// Start the game from the current world, or resume it if paused.
+    pub fn play(world: &mut World) {
+        if world.resource::<PlayMode>().state == PlayState::Stopped {
+            let entities = user_entities(world);
+            let snapshot = EntitiesSnapshot::capture(world, entities);
+            world.resource_mut::<UndoHistory>().checkpoint();
+            world.resource_mut::<PlayMode>().snapshot = Some(snapshot);
+            log::info!("Play");
+        }
+        world.resource_mut::<PlayMode>().state = PlayState::Playing;
+        world.resource_mut::<Time>().unpause();
+    }
+
+    pub fn pause(world: &mut World) {
+        let mut play_mode = world.resource_mut::<PlayMode>();
+        if play_mode.state == PlayState::Playing {
+            play_mode.state = PlayState::Paused;
+            world.resource_mut::<Time>().pause();
+        }
+    }
+
+    /// Pause the game and run it for one frame.
+    pub fn step(world: &mut World) {
+        if world.resource::<PlayMode>().state == PlayState::Stopped {
+            PlayMode::play(world);
+        }
+        PlayMode::pause(world);
+        world.resource_mut::<PlayMode>().step_requested = true;
+    }
+
+    /// Stop the game and restore the world captured by [`PlayMode::play`].
+    ///
+    /// The entities spawned while playing are despawned, the other ones are put back in their
+    /// state, so their components that aren't reflected are kept. The undo steps recorded while
+    /// playing are dropped with the changes they were made on.
+    pub fn stop(world: &mut World) {
+        let mut play_mode = world.resource_mut::<PlayMode>();
+        if play_mode.state == PlayState::Stopped {
+            return;
+        }
+        play_mode.state = PlayState::Stopped;
+        play_mode.step_requested = false;
+        let snapshot = play_mode.snapshot.take();
+        world.resource_mut::<Time>().pause();
+
+        if let Some(snapshot) = snapshot {
+            let captured: HashSet<Entity> = snapshot.entities().collect();
+            for entity in user_entities(world) {
+                if !captured.contains(&entity) {
+                    world.despawn(entity);
+                }
+            }
+            snapshot.restore(world);
+        }
+        world.resource_mut::<UndoHistory>().rollback();
+        log::info!("Stop");
+    }
+}
+
+fn user_entities(world: &mut World) -> Vec<Entity> {
+    world
+        .query_filtered::<Entity, Without<InspectorEntity>>()
+        .iter(world)
+        .collect()
+}
+
+/// Run condition of the game systems, see `GameSet`.
+pub fn game_running(play_mode: Res<PlayMode>) -> bool {
+    play_mode.state == PlayState::Playing || play_mode.stepping
+}
+
+/// The editor starts stopped, with the virtual time frozen.
+pub fn setup_play_mode(mut time: ResMut<Time>) {
+    time.pause();
+}
+
+/// Let the virtual time advance for the frame requested by [`PlayMode::step`].
+pub fn begin_step(mut play_mode: ResMut<PlayMode>, mut time: ResMut<Time>) {
+    if std::mem::take(&mut play_mode.step_requested) {
+        play_mode.stepping = true;
+        time.unpause();
+    }
+}
+
+pub fn end_step(mut play_mode: ResMut<PlayMode>, mut time: ResMut<Time>) {
+    if std::mem::take(&mut play_mode.stepping) {
+        time.pause();
+    }
+}
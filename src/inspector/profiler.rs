@@ -0,0 +1,482 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::utils::tracing::{self, span, Metadata, Subscriber};
+use bevy::utils::Instant;
+use serde::Serialize;
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use super::logger::{install_subscriber, SubscriberLayers};
+
+/// Frames kept for the Profiler tab.
+pub const PROFILED_FRAMES: usize = 120;
+
+/// Environment variable recording frames from the start of the app, as `frames[:path]`.
+pub const PROFILE_ENV: &str = "INSPECTOR_PROFILE";
+
+/// Records the duration of the schedules and systems of each frame.
+///
+/// The spans are only emitted with the `trace` feature of Bevy. Setting [`PROFILE_ENV`], e.g.
+/// `INSPECTOR_PROFILE=300:trace.json`, records that many frames to a Chrome trace and exits the
+/// app, which also works headless:
+///
+/// ```ignore
+/// App::new().add_plugins((MinimalPlugins, ProfilerPlugin)).add_systems(Update, game).run();
+/// ```
+pub struct ProfilerPlugin;
+
+impl Plugin for ProfilerPlugin {
+    fn build(&self, app: &mut App) {
+        let profiler = Profiler::default();
+        // each layer has its own filter, a layer disabling a callsite disables it for all of them
+        let layer = ProfilerLayer {
+            profiler: profiler.clone(),
+            epoch: Instant::now(),
+        };
+        SubscriberLayers::add(app, layer.with_filter(filter_fn(ProfilerLayer::profiled)));
+
+        if let Ok(value) = std::env::var(PROFILE_ENV) {
+            let (frames, path) = value.split_once(':').unwrap_or((&value, "trace.json"));
+            match frames.parse() {
+                Ok(frames) => profiler.record(frames, PathBuf::from(path), true),
+                Err(_) => log::error!("{PROFILE_ENV} should be `frames[:path]`, not `{value}`"),
+            }
+        }
+
+        app.insert_resource(profiler)
+            .add_systems(Last, write_recordings);
+    }
+
+    fn finish(&self, app: &mut App) {
+        install_subscriber(app);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpanKind {
+    Schedule,
+    System,
+}
+
+#[derive(Clone)]
+pub struct ProfiledSpan {
+    pub kind: SpanKind,
+    pub name: Arc<str>,
+    /// Schedule running the span, its own name for a schedule.
+    pub schedule: Arc<str>,
+    /// Since the profiler was installed.
+    pub start: Duration,
+    pub duration: Duration,
+    pub thread: u64,
+}
+
+#[derive(Clone)]
+pub struct ProfiledFrame {
+    pub index: u64,
+    /// Since the profiler was installed.
+    pub start: Duration,
+    pub duration: Duration,
+    pub spans: Vec<ProfiledSpan>,
+}
+
+#[derive(Resource, Clone)]
+pub struct Profiler {
+    /// Tells the buffers of the profilers apart in the threads.
+    id: u64,
+    data: Arc<Mutex<ProfilerData>>,
+    /// Buffer of every thread which ran a span.
+    threads: Arc<Mutex<Vec<SpanBuffer>>>,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        static NEXT_PROFILER: AtomicU64 = AtomicU64::new(0);
+        Self {
+            id: NEXT_PROFILER.fetch_add(1, Ordering::Relaxed),
+            data: default(),
+            threads: default(),
+        }
+    }
+}
+
+/// Spans of the frame being run on one thread, so that the threads don't wait for each other
+/// to record them. They are merged when the frame ends.
+type SpanBuffer = Arc<Mutex<Vec<ProfiledSpan>>>;
+
+#[derive(Default)]
+struct ProfilerData {
+    /// The kept frames aren't replaced while paused.
+    paused: bool,
+    next_frame: u64,
+    frame_start: Option<Duration>,
+    frames: VecDeque<ProfiledFrame>,
+    recording: Option<Recording>,
+    finished: Vec<Recording>,
+}
+
+/// Frames recorded for a Chrome trace.
+struct Recording {
+    path: PathBuf,
+    length: usize,
+    frames: Vec<ProfiledFrame>,
+    exit_when_done: bool,
+}
+
+impl Profiler {
+    /// Run `f` with the kept frames, from the oldest to the newest.
+    pub fn read<R>(&self, f: impl FnOnce(&VecDeque<ProfiledFrame>) -> R) -> R {
+        f(&self.data.lock().unwrap().frames)
+    }
+
+    pub fn paused(&self) -> bool {
+        self.data.lock().unwrap().paused
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.data.lock().unwrap().paused = paused;
+    }
+
+    /// Record the next `frames` frames to a Chrome trace at `path`.
+    pub fn record(&self, frames: usize, path: PathBuf, exit_when_done: bool) {
+        log::info!("Recording {frames} frames to {}", path.display());
+        self.data.lock().unwrap().recording = Some(Recording {
+            path,
+            length: frames,
+            frames: Vec::with_capacity(frames),
+            exit_when_done,
+        });
+    }
+
+    /// Frames recorded and to record, if a recording is in progress.
+    pub fn recording_progress(&self) -> Option<(usize, usize)> {
+        let data = self.data.lock().unwrap();
+        let recording = data.recording.as_ref()?;
+        Some((recording.frames.len(), recording.length))
+    }
+
+    /// Add a span to the buffer of the current thread.
+    fn push_span(&self, span: ProfiledSpan) {
+        thread_local! {
+            static BUFFERS: RefCell<Vec<(u64, SpanBuffer)>> = RefCell::default();
+        }
+        BUFFERS.with(|buffers| {
+            let mut buffers = buffers.borrow_mut();
+            let index = match buffers.iter().position(|(id, _)| *id == self.id) {
+                Some(index) => index,
+                None => {
+                    let buffer = SpanBuffer::default();
+                    self.threads.lock().unwrap().push(buffer.clone());
+                    buffers.push((self.id, buffer));
+                    buffers.len() - 1
+                }
+            };
+            buffers[index].1.lock().unwrap().push(span);
+        });
+    }
+
+    fn end_frame(&self, now: Duration) {
+        let mut spans = Vec::new();
+        let mut threads = self.threads.lock().unwrap();
+        for buffer in threads.iter() {
+            spans.append(&mut buffer.lock().unwrap());
+        }
+        // the buffers of the threads which ended are only referenced here
+        threads.retain(|buffer| Arc::strong_count(buffer) > 1);
+        drop(threads);
+
+        let mut data = self.data.lock().unwrap();
+        let start = data.frame_start.take().unwrap_or(now);
+        let frame = ProfiledFrame {
+            index: data.next_frame,
+            start,
+            duration: now.saturating_sub(start),
+            spans,
+        };
+        data.next_frame += 1;
+
+        if let Some(recording) = &mut data.recording {
+            recording.frames.push(frame.clone());
+            if recording.frames.len() >= recording.length {
+                let recording = data.recording.take().unwrap();
+                data.finished.push(recording);
+            }
+        }
+        if !data.paused {
+            if data.frames.len() >= PROFILED_FRAMES {
+                data.frames.pop_front();
+            }
+            data.frames.push_back(frame);
+        }
+    }
+}
+
+/// Complete event of a Chrome trace, with its times in microseconds.
+#[derive(Serialize)]
+struct TraceEvent<'a> {
+    name: &'a str,
+    cat: &'a str,
+    ph: &'static str,
+    ts: f64,
+    dur: f64,
+    pid: u32,
+    tid: u64,
+}
+
+/// Chrome trace of `frames`, openable in `chrome://tracing` or Perfetto.
+pub fn chrome_trace(frames: &[ProfiledFrame]) -> String {
+    let event = |name: &str, category: &str, start: Duration, duration: Duration, thread| {
+        let event = TraceEvent {
+            name,
+            cat: category,
+            ph: "X",
+            ts: start.as_secs_f64() * 1e6,
+            dur: duration.as_secs_f64() * 1e6,
+            pid: 1,
+            tid: thread,
+        };
+        // strings and numbers always serialize
+        serde_json::to_string(&event).unwrap_or_default()
+    };
+
+    let mut events = Vec::new();
+    for frame in frames {
+        // the frames get their own row, the threads start at 1
+        let name = format!("frame {}", frame.index);
+        events.push(event(&name, "frame", frame.start, frame.duration, 0));
+        for span in &frame.spans {
+            events.push(event(
+                &span.name,
+                &span.schedule,
+                span.start,
+                span.duration,
+                span.thread,
+            ));
+        }
+    }
+    format!("{{\"traceEvents\":[\n{}\n]}}\n", events.join(",\n"))
+}
+
+/// Write the finished recordings, and exit if one of them was started with [`PROFILE_ENV`].
+fn write_recordings(profiler: Res<Profiler>, mut app_exit: EventWriter<AppExit>) {
+    let finished = std::mem::take(&mut profiler.data.lock().unwrap().finished);
+    for recording in finished {
+        let path = &recording.path;
+        match std::fs::write(path, chrome_trace(&recording.frames)) {
+            Ok(()) => log::info!("{} frames recorded to {}", recording.length, path.display()),
+            Err(err) => log::error!("Failed to write the trace {}: {err}", path.display()),
+        }
+        if recording.exit_when_done {
+            app_exit.send(AppExit);
+        }
+    }
+}
+
+/// Small id of the current thread, the ids of the OS can be very large.
+fn thread_id() -> u64 {
+    static NEXT_THREAD: AtomicU64 = AtomicU64::new(1);
+    thread_local! {
+        static THREAD: u64 = NEXT_THREAD.fetch_add(1, Ordering::Relaxed);
+    }
+    THREAD.with(|thread| *thread)
+}
+
+/// Layer of the tracing subscriber timing the `update`, `schedule` and `system` spans of Bevy.
+struct ProfilerLayer {
+    profiler: Profiler,
+    /// Origin of the times of the spans.
+    epoch: Instant,
+}
+
+/// Data stored in the extensions of the profiled spans.
+struct SpanData {
+    /// `None` for the `update` span, the whole frame.
+    kind: Option<SpanKind>,
+    name: Arc<str>,
+    schedule: Arc<str>,
+    entered: Option<Duration>,
+}
+
+/// The `name` field of a span.
+#[derive(Default)]
+struct NameVisitor(String);
+
+impl tracing::field::Visit for NameVisitor {
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        if field.name() == "name" {
+            self.0 = value.to_string();
+        }
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "name" {
+            self.0 = format!("{value:?}");
+        }
+    }
+}
+
+impl ProfilerLayer {
+    /// The timed spans, and the span of the multi-threaded executor: the systems it runs on other
+    /// threads only find their schedule through it.
+    fn profiled(metadata: &Metadata) -> bool {
+        metadata.is_span()
+            && matches!(
+                metadata.name(),
+                "update" | "schedule" | "system" | "multithreaded executor"
+            )
+    }
+
+    fn now(&self) -> Duration {
+        self.epoch.elapsed()
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for ProfilerLayer {
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut name = NameVisitor::default();
+        attrs.record(&mut name);
+        let name: Arc<str> = name.0.into();
+
+        let kind = match span.name() {
+            "update" => None,
+            "schedule" => Some(SpanKind::Schedule),
+            "system" => Some(SpanKind::System),
+            _ => return,
+        };
+        let schedule = match kind {
+            Some(SpanKind::Schedule) => name.clone(),
+            _ => span
+                .scope()
+                .skip(1)
+                .find(|parent| parent.name() == "schedule")
+                .and_then(|parent| {
+                    parent
+                        .extensions()
+                        .get::<SpanData>()
+                        .map(|data| data.name.clone())
+                })
+                .unwrap_or_else(|| "".into()),
+        };
+        span.extensions_mut().insert(SpanData {
+            kind,
+            name,
+            schedule,
+            entered: None,
+        });
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let now = self.now();
+        let mut extensions = span.extensions_mut();
+        let Some(data) = extensions.get_mut::<SpanData>() else {
+            return;
+        };
+        data.entered = Some(now);
+        if data.kind.is_none() {
+            self.profiler.data.lock().unwrap().frame_start = Some(now);
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let now = self.now();
+        let extensions = span.extensions();
+        let Some(data) = extensions.get::<SpanData>() else {
+            return;
+        };
+        let Some(kind) = data.kind else {
+            self.profiler.end_frame(now);
+            return;
+        };
+        let start = data.entered.unwrap_or(now);
+        self.profiler.push_span(ProfiledSpan {
+            kind,
+            name: data.name.clone(),
+            schedule: data.schedule.clone(),
+            start,
+            duration: now.saturating_sub(start),
+            thread: thread_id(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inspector::inspect_log::{LogLayer, Logs};
+
+    #[test]
+    fn profile_env_records_and_exits_with_the_layers_of_every_plugin() {
+        let path = std::env::temp_dir().join("inspector_profile_test.json");
+        let _ = std::fs::remove_file(&path);
+        std::env::set_var(PROFILE_ENV, format!("3:{}", path.display()));
+
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, ProfilerPlugin))
+            .add_systems(Update, profiled_system);
+        // a layer added by a plugin built after the profiler, like the inspector
+        let logs = Logs::default();
+        let events = filter_fn(|metadata| metadata.is_event());
+        SubscriberLayers::add(&mut app, LogLayer(logs.clone()).with_filter(events));
+        app.finish();
+        std::env::remove_var(PROFILE_ENV);
+
+        let mut exited = false;
+        for _ in 0..10 {
+            app.update();
+            if !app.world.resource::<Events<AppExit>>().is_empty() {
+                exited = true;
+                break;
+            }
+        }
+        assert!(exited);
+        assert!(logs.count(log::Level::Info) >= 3);
+
+        let trace = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let trace: serde_json::Value = serde_json::from_str(&trace).unwrap();
+        let events = trace["traceEvents"].as_array().unwrap();
+        let frames: Vec<_> = events
+            .iter()
+            .filter(|event| event["cat"] == "frame")
+            .collect();
+        assert_eq!(frames.len(), 3);
+        assert!(frames.iter().all(|frame| frame["tid"] == 0));
+
+        let systems: Vec<_> = events
+            .iter()
+            .filter(|event| event["name"].as_str().unwrap().ends_with("profiled_system"))
+            .collect();
+        assert_eq!(systems.len(), 3);
+        let time = |event: &serde_json::Value, key: &str| event[key].as_f64().unwrap();
+        for (system, frame) in systems.iter().zip(&frames) {
+            assert_eq!(system["cat"], "Update");
+            assert_eq!(system["ph"], "X");
+            assert!(system["tid"].as_u64().unwrap() > 0);
+            // inside its frame
+            assert!(time(system, "ts") >= time(frame, "ts"));
+            let end = time(system, "ts") + time(system, "dur");
+            assert!(end <= time(frame, "ts") + time(frame, "dur") + 1e-6);
+        }
+    }
+
+    fn profiled_system() {
+        tracing::info!("updated");
+    }
+}
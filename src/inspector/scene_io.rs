@@ -0,0 +1,163 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use bevy::ecs::entity::EntityMap;
+use bevy::hierarchy::despawn_with_children_recursive;
+use bevy::prelude::*;
+use bevy::scene::serde::SceneDeserializer;
+use bevy::scene::SceneSpawnError;
+use serde::de::DeserializeSeed;
+
+use super::asset_handles::strengthen_handles;
+use super::default_scene::InspectorEntity;
+
+/// Directory where the scene files are stored, relative to the project root.
+pub const SCENES_DIR: &str = "assets";
+pub const SCENE_EXTENSION: &str = ".scn.ron";
+
+/// Every entity not tagged with [`InspectorEntity`], with its reflected components.
+pub fn capture_scene(world: &mut World) -> DynamicScene {
+    let entities: Vec<_> = world
+        .query_filtered::<Entity, Without<InspectorEntity>>()
+        .iter(world)
+        .collect();
+
+    let mut builder = DynamicSceneBuilder::from_world(world);
+    builder
+        .extract_entities(entities.into_iter())
+        .remove_empty_entities();
+    builder.build()
+}
+
+/// Serialize every entity not tagged with [`InspectorEntity`] into a `.scn.ron` file.
+pub fn save_scene(world: &mut World, path: &Path) -> Result<(), Box<dyn Error>> {
+    let scene = capture_scene(world);
+    let serialized = scene.serialize_ron(world.resource::<AppTypeRegistry>())?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serialized)?;
+    Ok(())
+}
+
+/// Deserialize a scene in the RON format, like the `.scn.ron` files.
+pub fn parse_scene(world: &World, bytes: &[u8]) -> Result<DynamicScene, Box<dyn Error>> {
+    let type_registry = world.resource::<AppTypeRegistry>().read();
+    let mut deserializer = ron::de::Deserializer::from_bytes(bytes)?;
+    let scene = SceneDeserializer {
+        type_registry: &type_registry,
+    }
+    .deserialize(&mut deserializer)?;
+    Ok(scene)
+}
+
+/// Replace the user entities of the world with the content of a `.scn.ron` file.
+pub fn load_scene(world: &mut World, path: &Path) -> Result<(), Box<dyn Error>> {
+    let bytes = std::fs::read(path)?;
+    let scene = parse_scene(world, &bytes)?;
+
+    clear_scene(world);
+    let mut entity_map = EntityMap::default();
+    scene.write_to_world(world, &mut entity_map)?;
+    strengthen_handles(world, entity_map.values());
+    Ok(())
+}
+
+/// Despawn every root entity (and its children) not tagged with [`InspectorEntity`].
+pub fn clear_scene(world: &mut World) {
+    let roots: Vec<_> = world
+        .query_filtered::<Entity, (Without<Parent>, Without<InspectorEntity>)>()
+        .iter(world)
+        .collect();
+    for entity in roots {
+        despawn_with_children_recursive(world, entity);
+    }
+}
+
+/// `roots` followed by all their descendants.
+pub fn with_descendants(world: &World, roots: impl IntoIterator<Item = Entity>) -> Vec<Entity> {
+    let mut entities: Vec<Entity> = roots.into_iter().collect();
+    let mut index = 0;
+    while let Some(&entity) = entities.get(index) {
+        if let Some(children) = world.get::<Children>(entity) {
+            entities.extend(children.iter().copied());
+        }
+        index += 1;
+    }
+    entities
+}
+
+/// The entities of `entities` without an ancestor in `entities`.
+pub fn topmost(world: &World, entities: &[Entity]) -> Vec<Entity> {
+    entities
+        .iter()
+        .copied()
+        .filter(|&entity| {
+            let mut ancestors = std::iter::successors(world.get::<Parent>(entity), |parent| {
+                world.get::<Parent>(parent.get())
+            });
+            world.get_entity(entity).is_some()
+                && !ancestors.any(|parent| entities.contains(&parent.get()))
+        })
+        .collect()
+}
+
+/// `roots` and their descendants, the roots being detached from their parents.
+pub fn extract_subtrees(world: &World, roots: &[Entity]) -> DynamicScene {
+    let mut builder = DynamicSceneBuilder::from_world(world);
+    builder.extract_entities(with_descendants(world, roots.iter().copied()).into_iter());
+    let mut scene = builder.build();
+    for entity in &mut scene.entities {
+        if roots.contains(&entity.entity) {
+            entity
+                .components
+                .retain(|component| !is_parent(&**component));
+        }
+    }
+    scene
+}
+
+fn is_parent(component: &dyn Reflect) -> bool {
+    component.type_name() == std::any::type_name::<Parent>()
+}
+
+/// Spawn new entities from `scene`, returns its root entities with their copy.
+pub fn spawn_copy(
+    world: &mut World,
+    scene: &DynamicScene,
+) -> Result<Vec<(Entity, Entity)>, SceneSpawnError> {
+    let mut entity_map = EntityMap::default();
+    scene.write_to_world(world, &mut entity_map)?;
+    let roots = scene
+        .entities
+        .iter()
+        .filter(|entity| {
+            !entity
+                .components
+                .iter()
+                .any(|component| is_parent(&**component))
+        })
+        .filter_map(|entity| Some((entity.entity, entity_map.get(entity.entity)?)))
+        .collect();
+    Ok(roots)
+}
+
+/// List the scene files inside `dir` and its subdirectories, sorted by path.
+pub fn list_scene_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.to_string_lossy().ends_with(SCENE_EXTENSION) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
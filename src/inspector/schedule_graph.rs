@@ -0,0 +1,365 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use bevy::ecs::component::Components;
+use bevy::ecs::schedule::{NodeId, Schedules};
+use bevy::prelude::*;
+use bevy::utils::get_short_name;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+    System,
+    Set,
+}
+
+pub struct GraphNode {
+    pub kind: NodeKind,
+    pub name: String,
+    /// Sets containing the node.
+    pub parents: Vec<usize>,
+    /// Systems and sets of a set.
+    pub children: Vec<usize>,
+    /// Nodes explicitly ordered after this one.
+    pub before: Vec<usize>,
+    /// Nodes explicitly ordered before this one.
+    pub after: Vec<usize>,
+}
+
+/// Two systems with conflicting accesses and no order between them.
+pub struct Ambiguity {
+    pub first: usize,
+    pub second: usize,
+    /// Components or resources written by one of them, empty if one of them is exclusive.
+    pub components: Vec<String>,
+}
+
+/// Systems and sets of a schedule, with their explicit ordering.
+///
+/// The sets made for each system function are merged into their system.
+pub struct ScheduleSnapshot {
+    pub nodes: Vec<GraphNode>,
+    pub ambiguities: Vec<Ambiguity>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Refresh {
+    Idle,
+    Requested,
+    /// Every schedule was snapshotted but `Last`, which was running.
+    LastMissing,
+}
+
+/// Snapshots of the schedules for the Schedules tab.
+///
+/// A schedule is out of [`Schedules`] while it runs, so they are snapshotted in `Last` and then
+/// `Last` itself in the next `First`. `Main` is always running and is never snapshotted.
+#[derive(Resource)]
+pub struct ScheduleGraphs {
+    pub schedules: BTreeMap<String, ScheduleSnapshot>,
+    refresh: Refresh,
+}
+
+impl Default for ScheduleGraphs {
+    fn default() -> Self {
+        Self {
+            schedules: BTreeMap::new(),
+            refresh: Refresh::Requested,
+        }
+    }
+}
+
+impl ScheduleGraphs {
+    /// Snapshot the schedules again, e.g. after systems were added.
+    pub fn refresh(&mut self) {
+        self.refresh = Refresh::Requested;
+    }
+
+    pub fn refreshing(&self) -> bool {
+        self.refresh != Refresh::Idle
+    }
+}
+
+pub fn snapshot_schedules(world: &mut World) {
+    if world.resource::<ScheduleGraphs>().refresh != Refresh::Requested {
+        return;
+    }
+    let snapshots: Vec<(String, ScheduleSnapshot)> = world
+        .resource::<Schedules>()
+        .iter()
+        .map(|(label, schedule)| (format!("{label:?}"), snapshot(schedule, world.components())))
+        .collect();
+    let mut graphs = world.resource_mut::<ScheduleGraphs>();
+    graphs.schedules.extend(snapshots);
+    graphs.refresh = Refresh::LastMissing;
+}
+
+pub fn snapshot_last_schedule(world: &mut World) {
+    if world.resource::<ScheduleGraphs>().refresh != Refresh::LastMissing {
+        return;
+    }
+    let snapshot = world
+        .resource::<Schedules>()
+        .get(&Last)
+        .map(|schedule| snapshot(schedule, world.components()));
+    let mut graphs = world.resource_mut::<ScheduleGraphs>();
+    if let Some(snapshot) = snapshot {
+        graphs.schedules.insert(format!("{Last:?}"), snapshot);
+    }
+    graphs.refresh = Refresh::Idle;
+}
+
+/// Name of the function of a `SystemTypeSet`.
+fn system_type_name(set: &dyn SystemSet) -> String {
+    let debug = format!("{set:?}");
+    let name = debug
+        .strip_prefix("SystemTypeSet(\"")
+        .and_then(|name| name.strip_suffix("\")"))
+        .unwrap_or(&debug);
+    get_short_name(name)
+}
+
+fn snapshot(schedule: &Schedule, components: &Components) -> ScheduleSnapshot {
+    let graph = schedule.graph();
+    let hierarchy = graph.hierarchy().graph();
+
+    let mut children: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+    for (parent, child, _) in hierarchy.all_edges() {
+        children.entry(parent).or_default().push(child);
+    }
+
+    // the sets of a system function with a single system are merged into it
+    let mut merged: HashMap<NodeId, NodeId> = HashMap::new();
+    let mut type_names: HashMap<NodeId, Vec<String>> = HashMap::new();
+    for (id, set, _) in graph.system_sets() {
+        if set.system_type().is_none() {
+            continue;
+        }
+        if let [system] = children.get(&id).map(Vec::as_slice).unwrap_or_default() {
+            if system.is_system() {
+                merged.insert(id, *system);
+                type_names
+                    .entry(*system)
+                    .or_default()
+                    .push(system_type_name(set));
+            }
+        }
+    }
+
+    let mut ids: Vec<NodeId> = hierarchy
+        .nodes()
+        .filter(|id| !merged.contains_key(id))
+        .collect();
+    ids.sort();
+    let mut indices: HashMap<NodeId, usize> = ids
+        .iter()
+        .enumerate()
+        .map(|(index, id)| (*id, index))
+        .collect();
+    for (set, system) in &merged {
+        indices.insert(*set, indices[system]);
+    }
+
+    let mut nodes: Vec<GraphNode> = ids
+        .iter()
+        .map(|&id| {
+            let (kind, name) = match id {
+                NodeId::System(index) => {
+                    // the systems are moved out of the graph once the schedule is built
+                    let name = match (graph.get_system_at(id), type_names.get(&id)) {
+                        (Some(system), _) => get_short_name(&system.name()),
+                        (None, Some(names)) => names.join(" | "),
+                        (None, None) => format!("system #{index}"),
+                    };
+                    (NodeKind::System, name)
+                }
+                NodeId::Set(_) => {
+                    let set = graph.set_at(id);
+                    let name = match set.system_type() {
+                        Some(_) => system_type_name(set),
+                        None => format!("{set:?}"),
+                    };
+                    (NodeKind::Set, name)
+                }
+            };
+            GraphNode {
+                kind,
+                name,
+                parents: Vec::new(),
+                children: Vec::new(),
+                before: Vec::new(),
+                after: Vec::new(),
+            }
+        })
+        .collect();
+
+    for (parent, child, _) in hierarchy.all_edges() {
+        if merged.contains_key(&parent) {
+            continue;
+        }
+        let (parent, child) = (indices[&parent], indices[&child]);
+        nodes[parent].children.push(child);
+        nodes[child].parents.push(parent);
+    }
+    for (before, after, _) in graph.dependency().graph().all_edges() {
+        let (before, after) = (indices[&before], indices[&after]);
+        if before != after && !nodes[before].before.contains(&after) {
+            nodes[before].before.push(after);
+            nodes[after].after.push(before);
+        }
+    }
+
+    let ambiguities = graph
+        .conflicting_systems()
+        .iter()
+        .map(|(first, second, conflicts)| Ambiguity {
+            first: indices[first],
+            second: indices[second],
+            components: conflicts
+                .iter()
+                .filter_map(|id| components.get_info(*id))
+                .map(|info| get_short_name(info.name()))
+                .collect(),
+        })
+        .collect();
+
+    ScheduleSnapshot { nodes, ambiguities }
+}
+
+/// `text` as a quoted Graphviz string.
+fn dot_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl ScheduleSnapshot {
+    /// Graphviz DOT of the schedule: the sets are dashed and linked to their content with dotted
+    /// edges, the ordering edges are solid and the ambiguities are red.
+    pub fn dot(&self, name: &str) -> String {
+        let mut dot = String::new();
+        let _ = writeln!(dot, "digraph {} {{", dot_string(name));
+        let _ = writeln!(dot, "    rankdir=LR;");
+        let _ = writeln!(dot, "    node [fontname=\"monospace\"];");
+        for (index, node) in self.nodes.iter().enumerate() {
+            let style = match node.kind {
+                NodeKind::System => "shape=box",
+                NodeKind::Set => "shape=box, style=\"dashed,rounded\"",
+            };
+            let _ = writeln!(
+                dot,
+                "    n{index} [label={}, {style}];",
+                dot_string(&node.name)
+            );
+        }
+        for (index, node) in self.nodes.iter().enumerate() {
+            for child in &node.children {
+                let _ = writeln!(dot, "    n{index} -> n{child} [style=dotted, color=gray];");
+            }
+            for after in &node.before {
+                let _ = writeln!(dot, "    n{index} -> n{after};");
+            }
+        }
+        for ambiguity in &self.ambiguities {
+            let label = if ambiguity.components.is_empty() {
+                "World".to_string()
+            } else {
+                ambiguity.components.join(", ")
+            };
+            let _ = writeln!(
+                dot,
+                "    n{} -> n{} [dir=none, color=red, fontcolor=red, label={}];",
+                ambiguity.first,
+                ambiguity.second,
+                dot_string(&label)
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+    struct Label(&'static str);
+
+    #[derive(Resource)]
+    struct Score;
+
+    fn read_score(_: Res<Score>) {}
+
+    fn write_score(_: ResMut<Score>) {}
+
+    fn bump_score(_: ResMut<Score>) {}
+
+    fn schedule_snapshot() -> ScheduleSnapshot {
+        let mut world = World::new();
+        world.insert_resource(Score);
+        let mut schedule = Schedule::new();
+        schedule.add_systems((
+            read_score.before(write_score),
+            write_score.in_set(Label("physics")),
+            bump_score,
+        ));
+        schedule.initialize(&mut world).unwrap();
+        snapshot(&schedule, world.components())
+    }
+
+    fn node(snapshot: &ScheduleSnapshot, name: &str) -> usize {
+        let mut nodes = snapshot.nodes.iter();
+        nodes.position(|node| node.name == name).unwrap()
+    }
+
+    #[test]
+    fn snapshot_has_the_sets_edges_and_ambiguities() {
+        let snapshot = schedule_snapshot();
+        // the sets of the system functions are merged into their systems
+        assert_eq!(snapshot.nodes.len(), 4);
+        let read = node(&snapshot, "read_score");
+        let write = node(&snapshot, "write_score");
+        let bump = node(&snapshot, "bump_score");
+        let set = node(&snapshot, "Label(\"physics\")");
+        assert_eq!(snapshot.nodes[set].kind, NodeKind::Set);
+        assert_eq!(snapshot.nodes[set].children, [write]);
+        assert_eq!(snapshot.nodes[write].parents, [set]);
+        assert_eq!(snapshot.nodes[read].before, [write]);
+        assert_eq!(snapshot.nodes[write].after, [read]);
+
+        let mut ambiguous: Vec<(usize, usize)> = snapshot
+            .ambiguities
+            .iter()
+            .map(|ambiguity| {
+                assert_eq!(ambiguity.components, ["Score"]);
+                let (first, second) = (ambiguity.first, ambiguity.second);
+                (first.min(second), first.max(second))
+            })
+            .collect();
+        ambiguous.sort();
+        let mut expected = [
+            (read.min(bump), read.max(bump)),
+            (write.min(bump), write.max(bump)),
+        ];
+        expected.sort();
+        assert_eq!(ambiguous, expected);
+    }
+
+    #[test]
+    fn dot_escapes_the_names() {
+        let snapshot = schedule_snapshot();
+        let dot = snapshot.dot("Update \"main\"");
+        let set = node(&snapshot, "Label(\"physics\")");
+        let read = node(&snapshot, "read_score");
+        let write = node(&snapshot, "write_score");
+        assert!(dot.starts_with("digraph \"Update \\\"main\\\"\" {\n"));
+        assert!(dot.contains(&format!(
+            "    n{set} [label=\"Label(\\\"physics\\\")\", shape=box, style=\"dashed,rounded\"];\n"
+        )));
+        assert!(dot.contains(&format!(
+            "    n{set} -> n{write} [style=dotted, color=gray];\n"
+        )));
+        assert!(dot.contains(&format!("    n{read} -> n{write};\n")));
+        assert_eq!(dot.matches("[dir=none, color=red").count(), 2);
+        assert!(dot.contains("label=\"Score\"];\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
@@ -0,0 +1,222 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use egui_dock::Tree;
+use egui_gizmo::{GizmoMode, GizmoOrientation};
+use serde::{Deserialize, Serialize};
+
+use super::camera_movement::FlycamControls;
+use super::default_scene::MainSceneCamera;
+use super::inspect_log::{LogFilter, Logs};
+use super::ui::{EguiWindow, GizmoSnapValues, LevelToggles, UiState};
+
+/// File storing the settings on native, relative to the project root.
+#[cfg(not(target_arch = "wasm32"))]
+const SETTINGS_FILE: &str = ".inspector.ron";
+/// Local storage key of the settings on wasm.
+#[cfg(target_arch = "wasm32")]
+const STORAGE_KEY: &str = "inspector_settings";
+
+/// Seconds between two checks for changed settings.
+const SAVE_INTERVAL: f32 = 5.0;
+
+/// Inspector state restored between sessions.
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InspectorSettings {
+    pub layout: Tree<EguiWindow>,
+    #[serde(with = "GizmoModeDef")]
+    pub gizmo_mode: GizmoMode,
+    #[serde(with = "GizmoOrientationDef")]
+    pub gizmo_orientation: GizmoOrientation,
+    pub gizmo_snap: GizmoSnapValues,
+    pub log_levels: LevelToggles,
+    /// Targets shown in the Console.
+    #[serde(with = "log_filter_directives")]
+    pub log_filter: LogFilter,
+    /// Command opening the location of a log, e.g. `code -g {file}:{line}`.
+    pub editor_command: String,
+    pub flycam: FlycamPose,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "GizmoMode")]
+enum GizmoModeDef {
+    Rotate,
+    Translate,
+    Scale,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "GizmoOrientation")]
+enum GizmoOrientationDef {
+    Global,
+    Local,
+}
+
+/// Saves a [`LogFilter`] as its directives, e.g. `"warn,game=trace"`.
+mod log_filter_directives {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::inspector::inspect_log::LogFilter;
+
+    pub fn serialize<S: Serializer>(filter: &LogFilter, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(filter)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<LogFilter, D::Error> {
+        LogFilter::parse(&String::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+/// Position and orientation of the Scene view camera.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct FlycamPose {
+    pub translation: [f32; 3],
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl Default for FlycamPose {
+    fn default() -> Self {
+        Self {
+            translation: [0., 3., 10.],
+            yaw: 0.,
+            pitch: -0.2,
+        }
+    }
+}
+
+impl FlycamPose {
+    pub fn transform(&self) -> Transform {
+        Transform::from_translation(Vec3::from(self.translation)).with_rotation(Quat::from_euler(
+            EulerRot::YXZ,
+            self.yaw,
+            self.pitch,
+            0.0,
+        ))
+    }
+}
+
+impl Default for InspectorSettings {
+    fn default() -> Self {
+        let ui_state = UiState::default();
+        Self {
+            layout: ui_state.tree,
+            gizmo_mode: ui_state.gizmo_mode,
+            gizmo_orientation: ui_state.gizmo_orientation,
+            gizmo_snap: ui_state.gizmo_snap,
+            log_levels: ui_state.console.levels,
+            log_filter: LogFilter::default(),
+            editor_command: ui_state.console.editor_command,
+            flycam: FlycamPose::default(),
+        }
+    }
+}
+
+impl InspectorSettings {
+    /// Settings saved by the last session, or the default ones.
+    pub fn load() -> Self {
+        let Some(ron) = read_settings() else {
+            return Self::default();
+        };
+        match ron::from_str(&ron) {
+            Ok(settings) => settings,
+            Err(err) => {
+                log::warn!("Can't read the inspector settings, using the default ones: {err}");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn apply(&self, ui_state: &mut UiState, logs: &Logs) {
+        ui_state.tree = self.layout.clone();
+        ui_state.gizmo_mode = self.gizmo_mode;
+        ui_state.gizmo_orientation = self.gizmo_orientation;
+        ui_state.gizmo_snap = self.gizmo_snap;
+        ui_state.console.levels = self.log_levels;
+        ui_state.console.editor_command = self.editor_command.clone();
+        logs.set_filter(self.log_filter.clone());
+    }
+
+    fn capture(
+        ui_state: &UiState,
+        logs: &Logs,
+        flycam: Option<(&FlycamControls, &Transform)>,
+    ) -> Self {
+        Self {
+            layout: ui_state.tree.clone(),
+            gizmo_mode: ui_state.gizmo_mode,
+            gizmo_orientation: ui_state.gizmo_orientation,
+            gizmo_snap: ui_state.gizmo_snap,
+            log_levels: ui_state.console.levels,
+            log_filter: logs.filter(),
+            editor_command: ui_state.console.editor_command.clone(),
+            flycam: flycam.map_or_else(FlycamPose::default, |(flycam, transform)| FlycamPose {
+                translation: transform.translation.into(),
+                yaw: flycam.yaw,
+                pitch: flycam.pitch,
+            }),
+        }
+    }
+}
+
+/// Save the settings when they changed, checked every [`SAVE_INTERVAL`] and when the app exits.
+pub fn save_settings(
+    time: Res<Time>,
+    ui_state: Res<UiState>,
+    logs: Res<Logs>,
+    flycam: Query<(&FlycamControls, &Transform), With<MainSceneCamera>>,
+    mut app_exit: EventReader<AppExit>,
+    mut last_check: Local<f32>,
+    mut last_saved: Local<String>,
+) {
+    let exiting = app_exit.iter().last().is_some();
+    if !exiting && time.raw_elapsed_seconds() - *last_check < SAVE_INTERVAL {
+        return;
+    }
+    *last_check = time.raw_elapsed_seconds();
+
+    let settings = InspectorSettings::capture(&ui_state, &logs, flycam.get_single().ok());
+    let ron = match ron::ser::to_string_pretty(&settings, ron::ser::PrettyConfig::default()) {
+        Ok(ron) => ron,
+        Err(err) => {
+            log::error!("Can't serialize the inspector settings: {err}");
+            return;
+        }
+    };
+    if ron != *last_saved {
+        write_settings(&ron);
+        *last_saved = ron;
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_settings() -> Option<String> {
+    std::fs::read_to_string(SETTINGS_FILE).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_settings(ron: &str) {
+    if let Err(err) = std::fs::write(SETTINGS_FILE, ron) {
+        log::error!("Can't save the inspector settings to {SETTINGS_FILE}: {err}");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_settings() -> Option<String> {
+    local_storage()?.get_item(STORAGE_KEY).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_settings(ron: &str) {
+    let saved = local_storage().map(|storage| storage.set_item(STORAGE_KEY, ron));
+    if !matches!(saved, Some(Ok(()))) {
+        log::error!("Can't save the inspector settings to the local storage");
+    }
+}
@@ -6,16 +6,39 @@ use bevy_inspector_egui::bevy_inspector::hierarchy::SelectedEntities;
 use egui_dock::{DockArea, NodeIndex, Tree};
 use egui_gizmo::{GizmoMode, GizmoOrientation};
 
+use super::overlays::OverlaySettings;
+use super::undo::UndoHistory;
+
 pub use add::*;
+pub use archetypes::*;
+pub use assets_browser::*;
+pub use command_line::*;
+pub use diagnostics::*;
+pub use edit_menu::*;
+pub use entity_menu::*;
 pub use gizmos::*;
 pub use hierarchy::*;
+pub use play_controls::*;
+pub use profiler::*;
+pub use scene_menu::*;
+pub use schedules::*;
 pub use select::*;
 pub use tab_viewer::*;
 pub use widgets::*;
 
 mod add;
+mod archetypes;
+mod assets_browser;
+mod command_line;
+mod diagnostics;
+mod edit_menu;
+mod entity_menu;
 mod gizmos;
 mod hierarchy;
+mod play_controls;
+mod profiler;
+mod scene_menu;
+mod schedules;
 mod select;
 mod tab_viewer;
 mod widgets;
@@ -33,13 +56,26 @@ pub struct UiState {
     pub gizmo_mode: GizmoMode,
     pub gizmo_snap: GizmoSnapValues,
     pub gizmo_orientation: GizmoOrientation,
+    pub precise_picking: bool,
+    pub overlays: OverlaySettings,
     pub hierarchy_state: AddWindowState<'static>,
-    pub filter_level_log: log::Level,
+    pub hierarchy_view: HierarchyView,
+    pub console: ConsoleState,
+    pub command_line: CommandLineState,
+    pub diagnostics: DiagnosticsState,
+    pub profiler: ProfilerState,
+    pub schedules: SchedulesState,
+    pub archetypes: ArchetypesState,
     pub exist_game_camera: bool,
+    pub scene_menu: SceneMenuState,
+    pub assets_browser: AssetsBrowserState,
 }
 
 impl UiState {
     fn ui(&mut self, world: &mut World, ctx: &mut egui::Context) {
+        // continuous edits are merged until the user releases the mouse or the focused field
+        world.resource_mut::<UndoHistory>().end_merge_on_input(ctx);
+
         let mut tab_viewer = TabViewer {
             world,
             exist_game_camera: self.exist_game_camera,
@@ -53,14 +89,43 @@ impl UiState {
             gizmo_mode: &mut self.gizmo_mode,
             gizmo_snap: &mut self.gizmo_snap,
             gizmo_orientation: &mut self.gizmo_orientation,
+            precise_picking: &mut self.precise_picking,
+            overlays: &mut self.overlays,
             hierarchy_state: &self.hierarchy_state,
-            filter_level_log: &mut self.filter_level_log,
+            hierarchy_view: &mut self.hierarchy_view,
+            console: &mut self.console,
+            command_line: &mut self.command_line,
+            diagnostics: &mut self.diagnostics,
+            profiler: &mut self.profiler,
+            schedules: &mut self.schedules,
+            archetypes: &mut self.archetypes,
+            assets_browser: &mut self.assets_browser,
         };
 
+        if let Some(action) = EditAction::from_shortcuts(ctx) {
+            action.run(tab_viewer.world, tab_viewer.selected_entities);
+        }
+        if let Some(action) = EntityAction::from_shortcuts(ctx) {
+            if action.run(ctx, tab_viewer.world, tab_viewer.selected_entities) {
+                *tab_viewer.selection = InspectorSelection::Entities;
+            }
+        }
+        if let Some(action) = PlayAction::from_shortcuts(ctx) {
+            action.run(tab_viewer.world, tab_viewer.selected_entities);
+        }
+        if let Some(action) = SceneAction::from_shortcuts(ctx) {
+            self.scene_menu
+                .run(tab_viewer.world, tab_viewer.selected_entities, action);
+        }
+        self.scene_menu
+            .show_dialog(ctx, tab_viewer.world, tab_viewer.selected_entities);
+
         egui::CentralPanel::default()
             .frame(egui::Frame::central_panel(&ctx.style()).inner_margin(0.))
             .show(ctx, |ui| {
-                egui::menu::bar(ui, |ui| file_menu_button(ui, &mut self.tree));
+                egui::menu::bar(ui, |ui| {
+                    file_menu_button(ui, &mut self.tree, &mut self.scene_menu, &mut tab_viewer)
+                });
                 ui.horizontal(|ui| tools_menu(ui, &mut tab_viewer));
 
                 DockArea::new(&mut self.tree).show_inside(ui, &mut tab_viewer);
@@ -68,28 +133,31 @@ impl UiState {
     }
 }
 
+/// Default arrangement of the tabs, restored by "Reset Layout".
+pub fn default_layout() -> Tree<EguiWindow> {
+    let mut tree = Tree::new(vec![EguiWindow::SceneView, EguiWindow::GameView]);
+    let [game, _inspector] = tree.split_right(NodeIndex::root(), 0.75, vec![EguiWindow::Inspector]);
+    let [game, _hierarchy] = tree.split_left(
+        game,
+        0.2,
+        vec![EguiWindow::Hierarchy, EguiWindow::RuntimeAssets],
+    );
+    let [_game, _bottom] = tree.split_below(
+        game,
+        0.8,
+        vec![
+            EguiWindow::Resources,
+            EguiWindow::Assets,
+            EguiWindow::Console,
+        ],
+    );
+    tree
+}
+
 impl Default for UiState {
     fn default() -> Self {
-        let mut tree = Tree::new(vec![EguiWindow::SceneView, EguiWindow::GameView]);
-        let [game, _inspector] =
-            tree.split_right(NodeIndex::root(), 0.75, vec![EguiWindow::Inspector]);
-        let [game, _hierarchy] = tree.split_left(
-            game,
-            0.2,
-            vec![EguiWindow::Hierarchy, EguiWindow::RuntimeAssets],
-        );
-        let [_game, _bottom] = tree.split_below(
-            game,
-            0.8,
-            vec![
-                EguiWindow::Resources,
-                EguiWindow::Assets,
-                EguiWindow::Console,
-            ],
-        );
-
         Self {
-            tree,
+            tree: default_layout(),
             exist_game_camera: false,
             selected_entities: SelectedEntities::default(),
             selection: InspectorSelection::Entities,
@@ -98,11 +166,21 @@ impl Default for UiState {
             game_render: None,
             scene_texture_id: None,
             game_texture_id: None,
-            filter_level_log: log::max_level().to_level().unwrap_or(log::Level::Trace),
+            console: ConsoleState::default(),
+            command_line: CommandLineState::default(),
+            diagnostics: DiagnosticsState::default(),
+            profiler: ProfilerState::default(),
+            schedules: SchedulesState::default(),
+            archetypes: ArchetypesState::default(),
             hierarchy_state: AddWindowState::default(),
+            hierarchy_view: HierarchyView::default(),
             gizmo_snap: GizmoSnapValues::default(),
             gizmo_mode: GizmoMode::Translate,
             gizmo_orientation: GizmoOrientation::Local,
+            precise_picking: false,
+            overlays: OverlaySettings::default(),
+            scene_menu: SceneMenuState::default(),
+            assets_browser: AssetsBrowserState::default(),
         }
     }
 }
@@ -121,23 +199,71 @@ pub fn show_ui_system(world: &mut World) {
     });
 }
 
-fn file_menu_button(ui: &mut egui::Ui, tree: &mut Tree<EguiWindow>) {
-    let save_shortcut = egui::KeyboardShortcut::new(egui::Modifiers::CTRL, egui::Key::S);
-
+fn file_menu_button(
+    ui: &mut egui::Ui,
+    tree: &mut Tree<EguiWindow>,
+    scene_menu: &mut SceneMenuState,
+    tab_viewer: &mut TabViewer,
+) {
     ui.menu_button("File", |ui| {
         ui.set_min_width(220.0);
         ui.style_mut().wrap = Some(false);
 
-        if ui
-            .add(
-                egui::Button::new("Save Scene")
-                    .shortcut_text(ui.ctx().format_shortcut(&save_shortcut)),
-            )
-            .clicked()
-        {
-            // TODO: open file explorer if not have file.scn.ron
-            log::info!("Scene Saved!");
-            ui.close_menu();
+        for action in SceneAction::ALL {
+            if action == SceneAction::Save {
+                ui.separator();
+            }
+            if ui
+                .add(
+                    egui::Button::new(action.label())
+                        .shortcut_text(ui.ctx().format_shortcut(&action.shortcut())),
+                )
+                .clicked()
+            {
+                scene_menu.run(tab_viewer.world, tab_viewer.selected_entities, action);
+                ui.close_menu();
+            }
+        }
+    });
+
+    ui.menu_button("Edit", |ui| {
+        ui.set_min_width(220.0);
+        ui.style_mut().wrap = Some(false);
+
+        for action in EditAction::ALL {
+            let step = action.step_name(tab_viewer.world);
+            let label = match &step {
+                Some(step) => format!("{} {step}", action.label()),
+                None => action.label().to_string(),
+            };
+            if ui
+                .add_enabled(
+                    step.is_some(),
+                    egui::Button::new(label)
+                        .shortcut_text(ui.ctx().format_shortcut(&action.shortcut())),
+                )
+                .clicked()
+            {
+                action.run(tab_viewer.world, tab_viewer.selected_entities);
+                ui.close_menu();
+            }
+        }
+
+        ui.separator();
+        for action in EntityAction::ALL {
+            if ui
+                .add_enabled(
+                    action.enabled(tab_viewer.selected_entities),
+                    egui::Button::new(action.label())
+                        .shortcut_text(ui.ctx().format_shortcut(&action.shortcut())),
+                )
+                .clicked()
+            {
+                if action.run(ui.ctx(), tab_viewer.world, tab_viewer.selected_entities) {
+                    *tab_viewer.selection = InspectorSelection::Entities;
+                }
+                ui.close_menu();
+            }
         }
     });
 
@@ -154,6 +280,10 @@ fn file_menu_button(ui: &mut egui::Ui, tree: &mut Tree<EguiWindow>) {
             EguiWindow::Resources,
             EguiWindow::Assets,
             EguiWindow::Console,
+            EguiWindow::Diagnostics,
+            EguiWindow::Profiler,
+            EguiWindow::Schedules,
+            EguiWindow::Archetypes,
         ];
 
         for btn in buttons {
@@ -162,6 +292,12 @@ fn file_menu_button(ui: &mut egui::Ui, tree: &mut Tree<EguiWindow>) {
                 ui.close_menu();
             }
         }
+
+        ui.separator();
+        if ui.button("Reset Layout").clicked() {
+            *tree = default_layout();
+            ui.close_menu();
+        }
     });
 }
 
@@ -265,5 +401,36 @@ fn tools_menu(ui: &mut egui::Ui, tab_viewer: &mut TabViewer) {
         {
             tab_viewer.gizmo_snap.enable = !tab_viewer.gizmo_snap.enable;
         }
+
+        if ui
+            .add(
+                egui::Button::new("Mesh")
+                    .fill(if *tab_viewer.precise_picking {
+                        egui::Color32::DARK_GRAY
+                    } else {
+                        egui::Color32::TRANSPARENT
+                    })
+                    .min_size(egui::Vec2::splat(18.0))
+                    .rounding(egui::Rounding::none()),
+            )
+            .on_hover_text("Pick by mesh triangles instead of bounding boxes")
+            .clicked()
+        {
+            *tab_viewer.precise_picking = !*tab_viewer.precise_picking;
+        }
     }
+
+    ui.add_space(10.);
+
+    ui.menu_button("Overlays", |ui| {
+        let overlays = &mut *tab_viewer.overlays;
+        ui.checkbox(&mut overlays.selection, "Selection");
+        ui.checkbox(&mut overlays.aabbs, "Bounding boxes");
+        ui.checkbox(&mut overlays.lights, "Light ranges");
+        ui.checkbox(&mut overlays.cameras, "Camera frustums");
+    });
+
+    ui.add_space(20.);
+
+    play_controls_ui(ui, tab_viewer.world, tab_viewer.selected_entities);
 }
@@ -1,4 +1,5 @@
 #![allow(dead_code)]
+use std::any::TypeId;
 use std::borrow::Cow;
 
 use bevy::render::camera::ScalingMode;
@@ -9,17 +10,23 @@ use bevy::{
     render::view::RenderLayers,
 };
 use bevy_inspector_egui::egui;
+use bevy_reflect::{TypeRegistration, TypeRegistry};
+
+use crate::inspector::undo::PendingEdit;
 
 pub struct AddItem {
     name: Cow<'static, str>,
-    add_to_entity: fn(&mut World, Entity),
+    add_to_entity: Box<dyn Fn(&mut World, Entity) + Send + Sync>,
 }
 
 impl AddItem {
-    pub fn new(name: Cow<'static, str>, add_to_entity: fn(&mut World, Entity)) -> Self {
+    pub fn new(
+        name: Cow<'static, str>,
+        add_to_entity: impl Fn(&mut World, Entity) + Send + Sync + 'static,
+    ) -> Self {
         AddItem {
             name,
-            add_to_entity,
+            add_to_entity: Box::new(add_to_entity),
         }
     }
 
@@ -45,39 +52,161 @@ impl AddItem {
         })
     }
 
+    /// Item for a type registered with `#[reflect(Component)]`, built from its
+    /// `ReflectDefault` if it has one or from its `FromWorld` implementation otherwise.
+    pub fn reflected(registration: &TypeRegistration) -> Option<Self> {
+        registration.data::<ReflectComponent>()?;
+        let type_id = registration.type_id();
+
+        Some(AddItem::new(
+            registration.short_name().to_string().into(),
+            move |world, entity| {
+                let type_registry = world.resource::<AppTypeRegistry>().clone();
+                let type_registry = type_registry.read();
+                let Some(registration) = type_registry.get(type_id) else {
+                    return;
+                };
+                let Some(reflect_component) = registration.data::<ReflectComponent>() else {
+                    return;
+                };
+
+                let component = match registration.data::<ReflectDefault>() {
+                    Some(reflect_default) => reflect_default.default(),
+                    None => reflect_component.from_world(world),
+                };
+                reflect_component.insert(&mut world.entity_mut(entity), &*component);
+            },
+        ))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn add_to_entity(&self, world: &mut World, entity: Entity) {
         (self.add_to_entity)(world, entity)
     }
 }
 
 pub struct AddWindowState<'a> {
-    sections: HashMap<&'a str, Vec<AddItem>>,
+    sections: HashMap<Cow<'a, str>, Vec<AddItem>>,
+    /// Sections shown first, the others follow in alphabetical order.
+    order: Vec<Cow<'a, str>>,
 }
 
 impl<'a> AddWindowState<'a> {
-    pub fn add(&mut self, name: &'a str, item: AddItem) {
-        self.sections.entry(name).or_default().push(item);
+    pub fn add(&mut self, name: impl Into<Cow<'a, str>>, item: AddItem) {
+        self.sections.entry(name.into()).or_default().push(item);
+    }
+
+    /// Show these sections first, in the given order, before the previously ordered ones.
+    pub fn set_order<S: Into<Cow<'a, str>>>(&mut self, sections: impl IntoIterator<Item = S>) {
+        let mut order: Vec<Cow<'a, str>> = sections.into_iter().map(Into::into).collect();
+        for name in self.order.drain(..) {
+            if !order.contains(&name) {
+                order.push(name);
+            }
+        }
+        self.order = order;
+    }
+
+    pub fn sections(&self) -> impl Iterator<Item = (&str, &[AddItem])> {
+        let position = |name: &Cow<str>| {
+            let position = self.order.iter().position(|ordered| ordered == name);
+            position.unwrap_or(usize::MAX)
+        };
+        let mut sections: Vec<_> = self.sections.iter().collect();
+        sections.sort_by(|(name_a, _), (name_b, _)| {
+            (position(name_a), name_a).cmp(&(position(name_b), name_b))
+        });
+        sections
+            .into_iter()
+            .map(|(name, items)| (name.as_ref(), items.as_slice()))
     }
 
-    pub fn sections(&self) -> impl Iterator<Item = (&'a str, &[AddItem])> {
-        self.sections
+    /// Add an item for every reflected component of the registry, in a section named after
+    /// its crate, see [`section_name`]. Components already present in another section are skipped.
+    pub fn add_registered_components(&mut self, type_registry: &TypeRegistry) {
+        // the hierarchy components are edited from the Hierarchy tab
+        let hierarchy = [TypeId::of::<Parent>(), TypeId::of::<Children>()];
+
+        let mut registrations: Vec<_> = type_registry
             .iter()
-            .map(|(name, items)| (*name, items.as_slice()))
+            .filter(|registration| !hierarchy.contains(&registration.type_id()))
+            .collect();
+        registrations.sort_by_key(|registration| registration.type_name());
+
+        for registration in registrations {
+            let name = registration.short_name();
+            let exists = self
+                .sections
+                .values()
+                .flatten()
+                .any(|item| item.name == name);
+            if exists {
+                continue;
+            }
+            if let Some(item) = AddItem::reflected(registration) {
+                self.add(section_name(registration.type_name()), item);
+            }
+        }
+    }
+}
+
+/// Sections of the components of the Bevy crates, the other crates get their own section.
+const CRATE_SECTIONS: [(&str, &str); 8] = [
+    ("bevy_core", "Core"),
+    ("bevy_transform", "Core"),
+    ("bevy_render", "Rendering"),
+    ("bevy_core_pipeline", "Rendering"),
+    ("bevy_pbr", "3D"),
+    ("bevy_sprite", "2D"),
+    ("bevy_text", "2D"),
+    ("bevy_ui", "UI"),
+];
+
+/// Section of a component from its crate: `bevy_pbr::light::PointLight` -> `3D`,
+/// `bevy_audio::audio::SpatialListener` -> `Audio`, the game components -> `Game`.
+fn section_name(type_name: &str) -> String {
+    let path = type_name.split('<').next().unwrap_or(type_name);
+    let krate = path.split("::").next().unwrap_or(path);
+    if let Some((_, section)) = CRATE_SECTIONS.iter().find(|(name, _)| *name == krate) {
+        return section.to_string();
+    }
+    if krate == crate::inspector::inspect_log::game_crate() {
+        return "Game".to_string();
+    }
+    match krate.strip_prefix("bevy_") {
+        // `bevy_window` -> `Window`
+        Some(name) => name
+            .split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars.next().map_or(String::new(), |first| {
+                    first.to_uppercase().chain(chars).collect()
+                })
+            })
+            .collect::<Vec<_>>()
+            .join(" "),
+        None => krate.to_string(),
     }
 }
 
 pub fn add_ui_button(world: &mut World, ui: &mut egui::Ui, state: &AddWindowState) {
     ui.menu_button("Add", |ui| {
         let _ = add_ui(ui, state).map(|add_item| {
+            let mut edit = PendingEdit::begin(world, format!("Add {}", add_item.name()), []);
             let entity = world.spawn_empty().id();
             add_item.add_to_entity(world, entity);
+            edit.track(entity);
+            edit.commit(world);
             entity
         });
     });
 }
 
 pub fn add_ui<'a>(ui: &mut egui::Ui, state: &'a AddWindowState) -> Option<&'a AddItem> {
-    for (section_name, items) in &state.sections {
+    for (section_name, items) in state.sections() {
         if section_name.is_empty() {
             for item in items {
                 if ui.button(item.name.as_ref()).clicked() {
@@ -86,7 +215,7 @@ pub fn add_ui<'a>(ui: &mut egui::Ui, state: &'a AddWindowState) -> Option<&'a Ad
                 }
             }
         } else {
-            let value = ui.menu_button(*section_name, |ui| {
+            let value = ui.menu_button(section_name, |ui| {
                 for item in items {
                     if ui.button(item.name.as_ref()).clicked() {
                         ui.close_menu();
@@ -107,7 +236,9 @@ impl<'a> Default for AddWindowState<'a> {
     fn default() -> Self {
         let mut state = AddWindowState {
             sections: HashMap::default(),
+            order: Vec::new(),
         };
+        state.set_order(["", "Core", "Rendering", "2D", "3D", "UI"]);
 
         state.add("", AddItem::bundle_named::<()>("Empty".into()));
 
@@ -192,3 +323,23 @@ impl<'a> Default for AddWindowState<'a> {
         state
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_are_named_after_the_crates() {
+        assert_eq!(section_name("bevy_pbr::light::PointLight"), "3D");
+        assert_eq!(section_name("bevy_ui::ui_node::Style"), "UI");
+        assert_eq!(section_name("bevy_window::window::PrimaryWindow"), "Window");
+        assert_eq!(
+            section_name("bevy_core_pipeline::tonemapping::Tonemapping"),
+            "Rendering"
+        );
+        assert_eq!(section_name("bevy_audio::audio::SpatialSettings"), "Audio");
+        let game = crate::inspector::inspect_log::game_crate();
+        assert_eq!(section_name(&format!("{game}::player::Player")), "Game");
+        assert_eq!(section_name("avian::Collider<f32>"), "avian");
+    }
+}
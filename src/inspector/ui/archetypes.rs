@@ -0,0 +1,283 @@
+use bevy::ecs::archetype::Archetype;
+use bevy::ecs::component::{ComponentId, StorageType};
+use bevy::prelude::*;
+use bevy::utils::get_short_name;
+use bevy_egui::egui::{self, Align, Color32, Layout, RichText};
+use bevy_inspector_egui::bevy_inspector::hierarchy::SelectedEntities;
+use egui_extras::{Column, TableBuilder};
+
+use crate::inspector::default_scene::InspectorEntity;
+
+/// Maximum number of components suggested by the component search.
+const MAX_SUGGESTIONS: usize = 12;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchetypeSort {
+    Id,
+    Entities,
+    Size,
+}
+
+pub struct ArchetypesState {
+    /// Components of the "find entities with" query, only their archetypes are listed.
+    with: Vec<ComponentId>,
+    search: String,
+    sort: ArchetypeSort,
+    hide_empty: bool,
+}
+
+impl Default for ArchetypesState {
+    fn default() -> Self {
+        Self {
+            with: Vec::new(),
+            search: String::new(),
+            sort: ArchetypeSort::Entities,
+            hide_empty: true,
+        }
+    }
+}
+
+fn component_name(world: &World, id: ComponentId) -> String {
+    match world.components().get_info(id) {
+        Some(info) => get_short_name(info.name()),
+        None => format!("{id:?}"),
+    }
+}
+
+/// Bytes of component data of an archetype, without the overhead of the storages.
+fn data_size(world: &World, archetype: &Archetype) -> usize {
+    archetype
+        .components()
+        .filter_map(|id| world.components().get_info(id))
+        .map(|info| info.layout().size() * archetype.len())
+        .sum()
+}
+
+fn format_size(bytes: usize) -> String {
+    match bytes {
+        0..=1023 => format!("{bytes} B"),
+        1024..=1_048_575 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+/// List the archetypes of the world, and find the entities having some components.
+///
+/// Returns `true` if the entity selection was changed.
+pub fn archetypes_ui(
+    ui: &mut egui::Ui,
+    world: &World,
+    state: &mut ArchetypesState,
+    selected: &mut SelectedEntities,
+) -> bool {
+    let mut select = None;
+
+    ui.horizontal(|ui| {
+        ui.label(format!(
+            "{} archetypes, {} tables, {} entities",
+            world.archetypes().len(),
+            world.storages().tables.len(),
+            world.entities().len()
+        ));
+        ui.separator();
+        ui.checkbox(&mut state.hide_empty, "Hide empty");
+        ui.separator();
+        ui.label("Sort by");
+        ui.selectable_value(&mut state.sort, ArchetypeSort::Id, "Id");
+        ui.selectable_value(&mut state.sort, ArchetypeSort::Entities, "Entities");
+        ui.selectable_value(&mut state.sort, ArchetypeSort::Size, "Size");
+    });
+
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Find entities with");
+        let mut removed = None;
+        for (index, id) in state.with.iter().enumerate() {
+            if ui
+                .button(format!("{} ✖", component_name(world, *id)))
+                .on_hover_text("Remove")
+                .clicked()
+            {
+                removed = Some(index);
+            }
+        }
+        if let Some(index) = removed {
+            state.with.remove(index);
+        }
+        ui.add(
+            egui::TextEdit::singleline(&mut state.search)
+                .hint_text("Component")
+                .desired_width(140.0),
+        );
+    });
+
+    let search = state.search.trim().to_lowercase();
+    if !search.is_empty() {
+        ui.horizontal_wrapped(|ui| {
+            let suggestions = world
+                .components()
+                .iter()
+                .filter(|info| !state.with.contains(&info.id()))
+                .filter(|info| info.name().to_lowercase().contains(&search))
+                .take(MAX_SUGGESTIONS)
+                .collect::<Vec<_>>();
+            for info in suggestions {
+                let name = get_short_name(info.name());
+                if ui.button(name).on_hover_text(info.name()).clicked() {
+                    state.with.push(info.id());
+                    state.search.clear();
+                }
+            }
+        });
+    }
+
+    // the archetypes are listed in the order of their ids
+    let mut archetypes: Vec<(usize, &Archetype)> = world
+        .archetypes()
+        .iter()
+        .enumerate()
+        .filter(|(_, archetype)| !state.hide_empty || !archetype.is_empty())
+        .filter(|(_, archetype)| state.with.iter().all(|id| archetype.contains(*id)))
+        .collect();
+    match state.sort {
+        ArchetypeSort::Id => {}
+        ArchetypeSort::Entities => {
+            archetypes.sort_by_key(|(_, archetype)| std::cmp::Reverse(archetype.len()))
+        }
+        ArchetypeSort::Size => {
+            archetypes.sort_by_key(|(_, archetype)| std::cmp::Reverse(data_size(world, archetype)))
+        }
+    }
+
+    // the entities of the editor are listed but never selected
+    let editor = world.component_id::<InspectorEntity>();
+    let is_editor = |archetype: &Archetype| editor.is_some_and(|id| archetype.contains(id));
+
+    if !state.with.is_empty() {
+        let (editor_count, count) = archetypes.iter().fold((0, 0), |(editor, game), (_, a)| {
+            if is_editor(a) {
+                (editor + a.len(), game)
+            } else {
+                (editor, game + a.len())
+            }
+        });
+        ui.horizontal(|ui| {
+            let mut label = format!("{count} entities in {} archetypes", archetypes.len());
+            if editor_count > 0 {
+                label += &format!(", and {editor_count} of the editor that can't be selected");
+            }
+            ui.label(label);
+            if ui
+                .add_enabled(count > 0, egui::Button::new("Select them"))
+                .clicked()
+            {
+                let archetypes = archetypes.iter().map(|(_, archetype)| *archetype);
+                select = Some(
+                    archetypes
+                        .filter(|&archetype| !is_editor(archetype))
+                        .collect(),
+                );
+            }
+        });
+    }
+    ui.separator();
+
+    TableBuilder::new(ui)
+        .striped(true)
+        .resizable(true)
+        .cell_layout(Layout::left_to_right(Align::Center))
+        .column(Column::initial(50.0).at_least(30.0))
+        .column(Column::initial(60.0).at_least(30.0))
+        .column(Column::initial(40.0).at_least(30.0))
+        .column(Column::initial(70.0).at_least(30.0))
+        .column(Column::initial(90.0).at_least(30.0))
+        .column(Column::remainder().clip(true))
+        .min_scrolled_height(0.0)
+        .header(20.0, |mut header| {
+            for title in ["Id", "Entities", "Table", "Size", "Storage", "Components"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(20.0, archetypes.len(), |index, mut row| {
+                let (id, archetype) = archetypes[index];
+                row.col(|ui| {
+                    if ui
+                        .add_enabled(
+                            !is_editor(archetype),
+                            egui::SelectableLabel::new(false, id.to_string()),
+                        )
+                        .on_hover_text("Select its entities")
+                        .on_disabled_hover_text("Entities of the editor, they can't be selected")
+                        .clicked()
+                    {
+                        select = Some(vec![archetype]);
+                    }
+                });
+                row.col(|ui| {
+                    ui.label(archetype.len().to_string());
+                });
+                row.col(|ui| {
+                    ui.label(archetype.table_id().index().to_string());
+                });
+                row.col(|ui| {
+                    ui.label(format_size(data_size(world, archetype)))
+                        .on_hover_text("Component data, without the overhead of the storages");
+                });
+                row.col(|ui| {
+                    let table = archetype.table_components().count();
+                    let sparse = archetype.sparse_set_components().count();
+                    ui.label(format!("{table} table, {sparse} sparse"));
+                });
+                row.col(|ui| components_ui(ui, world, archetype, &state.with));
+            });
+        });
+
+    let Some(archetypes) = select else {
+        return false;
+    };
+    selected.clear();
+    for archetype in archetypes {
+        for entity in archetype.entities() {
+            selected.select_maybe_add(entity.entity(), true);
+        }
+    }
+    true
+}
+
+/// Components of an archetype on one line, the sparse set ones marked with a ◆.
+fn components_ui(ui: &mut egui::Ui, world: &World, archetype: &Archetype, with: &[ComponentId]) {
+    let mut names: Vec<(String, bool, bool)> = archetype
+        .components()
+        .map(|id| {
+            let sparse = archetype.get_storage_type(id) == Some(StorageType::SparseSet);
+            (component_name(world, id), sparse, with.contains(&id))
+        })
+        .collect();
+    names.sort();
+
+    let response = ui
+        .horizontal(|ui| {
+            for (name, sparse, queried) in &names {
+                let mut text = RichText::new(if *sparse {
+                    format!("◆{name}")
+                } else {
+                    name.clone()
+                });
+                if *queried {
+                    text = text.strong();
+                } else if *sparse {
+                    text = text.color(Color32::LIGHT_BLUE);
+                }
+                ui.label(text);
+            }
+        })
+        .response;
+    response.on_hover_ui(|ui| {
+        for (name, sparse, _) in &names {
+            let storage = if *sparse { "sparse set" } else { "table" };
+            ui.label(format!("{name} ({storage})"));
+        }
+    });
+}
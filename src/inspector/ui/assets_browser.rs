@@ -0,0 +1,355 @@
+use std::path::{Path, PathBuf};
+
+use bevy::asset::{AssetPath, HandleId, ReflectAsset, SourcePathId};
+use bevy::prelude::*;
+use bevy_egui::egui;
+use bevy_inspector_egui::bevy_inspector::hierarchy::SelectedEntities;
+
+use crate::inspector::asset_handles::fields;
+use crate::inspector::undo::PendingEdit;
+
+/// Directory watched by the [`AssetServer`], relative to the project root.
+const ASSETS_DIR: &str = "assets";
+
+/// Cached content of a folder of the assets directory, paths are relative to [`ASSETS_DIR`].
+struct AssetDir {
+    path: PathBuf,
+    dirs: Vec<AssetDir>,
+    files: Vec<PathBuf>,
+}
+
+impl AssetDir {
+    fn scan(root: &Path, path: PathBuf) -> Self {
+        AssetDir::scan_inside(root, path, &mut Vec::new())
+    }
+
+    /// Scan `path` inside the directories `ancestors`, given by their canonical paths. The
+    /// symbolic links to an ancestor are skipped, they would be scanned forever.
+    fn scan_inside(root: &Path, path: PathBuf, ancestors: &mut Vec<PathBuf>) -> Self {
+        let mut dir = AssetDir {
+            path,
+            dirs: Vec::new(),
+            files: Vec::new(),
+        };
+        let full_path = root.join(&dir.path);
+        ancestors.push(canonical(&full_path));
+        if let Ok(entries) = std::fs::read_dir(&full_path) {
+            for entry in entries.flatten() {
+                let path = dir.path.join(entry.file_name());
+                if !entry.path().is_dir() {
+                    dir.files.push(path);
+                } else if ancestors.contains(&canonical(&entry.path())) {
+                    log::warn!("{} links to a parent folder", entry.path().display());
+                } else {
+                    dir.dirs.push(AssetDir::scan_inside(root, path, ancestors));
+                }
+            }
+        }
+        ancestors.pop();
+        dir.dirs.sort_by(|a, b| a.path.cmp(&b.path));
+        dir.files.sort();
+        dir
+    }
+
+    fn find(&self, path: &Path) -> Option<&AssetDir> {
+        if self.path == path {
+            return Some(self);
+        }
+        self.dirs.iter().find_map(|dir| dir.find(path))
+    }
+
+    fn all_files(&self) -> Box<dyn Iterator<Item = &PathBuf> + '_> {
+        Box::new(
+            self.files
+                .iter()
+                .chain(self.dirs.iter().flat_map(|dir| dir.all_files())),
+        )
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[derive(Default)]
+pub struct AssetsBrowserState {
+    root: Option<AssetDir>,
+    current_dir: PathBuf,
+    search: String,
+    /// Handles loaded from the browser, kept alive until the inspector is closed.
+    loaded: Vec<HandleUntyped>,
+}
+
+enum AssetAction {
+    SpawnScene(PathBuf),
+    Load(PathBuf),
+    RevealUsages(PathBuf),
+}
+
+/// Display the files of the assets directory.
+///
+/// Returns `true` if the entity selection was changed.
+pub fn assets_browser_ui(
+    ui: &mut egui::Ui,
+    world: &mut World,
+    state: &mut AssetsBrowserState,
+    selected: &mut SelectedEntities,
+) -> bool {
+    ui.horizontal(|ui| {
+        if ui.button("🔄").on_hover_text("Refresh").clicked() {
+            state.root = None;
+        }
+        ui.add(egui::TextEdit::singleline(&mut state.search).hint_text("Search"));
+    });
+    ui.separator();
+
+    if cfg!(target_arch = "wasm32") {
+        loaded_paths_ui(ui, world, &state.search);
+        return false;
+    }
+
+    let root = state
+        .root
+        .get_or_insert_with(|| AssetDir::scan(Path::new(ASSETS_DIR), PathBuf::new()));
+
+    egui::SidePanel::left("assets_browser_folders")
+        .resizable(true)
+        .default_width(160.0)
+        .show_inside(ui, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                folder_tree_ui(ui, root, &mut state.current_dir);
+            });
+        });
+
+    let mut action = None;
+    egui::CentralPanel::default().show_inside(ui, |ui| {
+        let search = state.search.to_lowercase();
+        let files: Vec<_> = if search.is_empty() {
+            root.find(&state.current_dir)
+                .map(|dir| dir.files.iter().collect())
+                .unwrap_or_default()
+        } else {
+            root.all_files()
+                .filter(|file| {
+                    file.to_string_lossy()
+                        .to_lowercase()
+                        .contains(search.as_str())
+                })
+                .collect()
+        };
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for file in files {
+                let id = HandleId::from(AssetPath::from(file.as_path()));
+                let loaded = state.loaded.iter().any(|handle| handle.id() == id);
+                let name = if search.is_empty() {
+                    file.file_name().unwrap_or_default().to_string_lossy()
+                } else {
+                    file.to_string_lossy()
+                };
+                ui.selectable_label(loaded, format!("{} {name}", file_icon(file)))
+                    .context_menu(|ui| {
+                        if is_scene(file) && ui.button("Spawn scene").clicked() {
+                            action = Some(AssetAction::SpawnScene(file.clone()));
+                            ui.close_menu();
+                        }
+                        if ui.button("Load as Handle").clicked() {
+                            action = Some(AssetAction::Load(file.clone()));
+                            ui.close_menu();
+                        }
+                        if ui.button("Reveal in hierarchy usages").clicked() {
+                            action = Some(AssetAction::RevealUsages(file.clone()));
+                            ui.close_menu();
+                        }
+                    });
+            }
+        });
+    });
+
+    match action {
+        Some(AssetAction::SpawnScene(path)) => {
+            let entity = spawn_scene(world, &path);
+            selected.select_replace(entity);
+            true
+        }
+        Some(AssetAction::Load(path)) => {
+            let handle = world.resource::<AssetServer>().load_untyped(path.as_path());
+            if !state.loaded.iter().any(|loaded| loaded.id() == handle.id()) {
+                log::info!("Loaded {}", path.display());
+                state.loaded.push(handle);
+            }
+            false
+        }
+        Some(AssetAction::RevealUsages(path)) => {
+            let usages = find_usages(world, &path);
+            log::info!("{} entities use {}", usages.len(), path.display());
+            selected.clear();
+            for entity in usages {
+                selected.select_maybe_add(entity, true);
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+fn folder_tree_ui(ui: &mut egui::Ui, dir: &AssetDir, current_dir: &mut PathBuf) {
+    let name = match dir.path.file_name() {
+        Some(name) => name.to_string_lossy(),
+        None => ASSETS_DIR.into(),
+    };
+    let selected = *current_dir == dir.path;
+    let label = format!("📁 {name}");
+
+    if dir.dirs.is_empty() {
+        if ui.selectable_label(selected, label).clicked() {
+            *current_dir = dir.path.clone();
+        }
+        return;
+    }
+
+    #[allow(deprecated)] // the suggested replacement doesn't really work
+    let response = egui::CollapsingHeader::new(label)
+        .id_source(&dir.path)
+        .default_open(dir.path.as_os_str().is_empty())
+        .selectable(true)
+        .selected(selected)
+        .show(ui, |ui| {
+            for child in &dir.dirs {
+                folder_tree_ui(ui, child, current_dir);
+            }
+        });
+    if response.header_response.clicked() {
+        *current_dir = dir.path.clone();
+    }
+}
+
+/// Read-only list of the paths of the loaded assets, used where the file system isn't available.
+fn loaded_paths_ui(ui: &mut egui::Ui, world: &World, search: &str) {
+    let type_registry = world.resource::<AppTypeRegistry>().read();
+    let asset_server = world.resource::<AssetServer>();
+    let search = search.to_lowercase();
+
+    let mut paths: Vec<_> = type_registry
+        .iter()
+        .filter_map(|registration| registration.data::<ReflectAsset>())
+        .flat_map(|reflect_asset| reflect_asset.ids(world))
+        .filter_map(|id| asset_server.get_handle_path(id))
+        .map(|path| path.path().to_path_buf())
+        .filter(|path| path.to_string_lossy().to_lowercase().contains(&search))
+        .collect();
+    paths.sort();
+    paths.dedup();
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for path in paths {
+            ui.label(format!("{} {}", file_icon(&path), path.display()));
+        }
+    });
+}
+
+fn is_scene(path: &Path) -> bool {
+    let name = path.to_string_lossy();
+    name.ends_with(".scn.ron") || name.ends_with(".scn") || is_gltf(path)
+}
+
+fn is_gltf(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("gltf" | "glb")
+    )
+}
+
+fn file_icon(path: &Path) -> &'static str {
+    if path.to_string_lossy().ends_with(".scn.ron") {
+        return "🎬";
+    }
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("png" | "jpg" | "jpeg" | "bmp" | "tga" | "ktx2" | "dds" | "hdr" | "basis") => "🖼",
+        Some("gltf" | "glb" | "obj") => "📦",
+        Some("ogg" | "wav" | "mp3" | "flac") => "🔊",
+        Some("ttf" | "otf") => "🔤",
+        Some("wgsl" | "glsl" | "vert" | "frag" | "spv") => "✨",
+        Some("ron" | "json" | "toml" | "txt") => "📝",
+        _ => "📄",
+    }
+}
+
+fn spawn_scene(world: &mut World, path: &Path) -> Entity {
+    let name = Name::new(
+        path.file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned(),
+    );
+    let mut edit = PendingEdit::begin(world, format!("Spawn {name}"), []);
+
+    let asset_server = world.resource::<AssetServer>();
+    let entity = if is_gltf(path) {
+        let scene = asset_server.load(format!("{}#Scene0", path.display()));
+        world.spawn((SceneBundle { scene, ..default() }, name)).id()
+    } else {
+        let scene = asset_server.load(path);
+        world
+            .spawn((DynamicSceneBundle { scene, ..default() }, name))
+            .id()
+    };
+
+    edit.track(entity);
+    edit.commit(world);
+    entity
+}
+
+/// Entities with a component holding a handle to the asset at `path` or one of its sub-assets.
+fn find_usages(world: &World, path: &Path) -> Vec<Entity> {
+    let source_path_id = SourcePathId::from(path);
+    let type_registry = world.resource::<AppTypeRegistry>().read();
+
+    world
+        .iter_entities()
+        .filter(|entity_ref| {
+            entity_ref.archetype().components().any(|component_id| {
+                world
+                    .components()
+                    .get_info(component_id)
+                    .and_then(|info| info.type_id())
+                    .and_then(|type_id| type_registry.get_type_data::<ReflectComponent>(type_id))
+                    .and_then(|reflect_component| reflect_component.reflect(*entity_ref))
+                    .is_some_and(|component| uses_source_path(component, source_path_id))
+            })
+        })
+        .map(|entity_ref| entity_ref.id())
+        .collect()
+}
+
+fn uses_source_path(value: &dyn Reflect, source_path_id: SourcePathId) -> bool {
+    match value.as_any().downcast_ref::<HandleId>() {
+        Some(HandleId::AssetPathId(id)) => id.source_path_id() == source_path_id,
+        _ => fields(value)
+            .into_iter()
+            .any(|field| uses_source_path(field, source_path_id)),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_to_a_parent_folder_are_skipped() {
+        let root = std::env::temp_dir().join(format!("assets_browser_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("models/shared")).unwrap();
+        std::fs::write(root.join("models/cube.glb"), []).unwrap();
+        std::os::unix::fs::symlink(&root, root.join("models/root")).unwrap();
+        std::os::unix::fs::symlink(root.join("models"), root.join("models/shared/up")).unwrap();
+
+        let dir = AssetDir::scan(&root, PathBuf::new());
+        std::fs::remove_dir_all(&root).unwrap();
+        let files: Vec<&PathBuf> = dir.all_files().collect();
+        assert_eq!(files, [&Path::new("models").join("cube.glb")]);
+        let models = dir.find(Path::new("models")).unwrap();
+        assert_eq!(models.dirs.len(), 1);
+        assert!(models.dirs[0].dirs.is_empty());
+    }
+}
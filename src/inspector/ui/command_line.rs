@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+use bevy_egui::egui::{self, Key};
+use bevy_inspector_egui::bevy_inspector::hierarchy::SelectedEntities;
+
+use crate::inspector::console_commands::{complete, run_command};
+
+use super::AddWindowState;
+
+#[derive(Default)]
+pub struct CommandLineState {
+    input: String,
+    /// Lines run before, from the oldest to the newest.
+    history: Vec<String>,
+    /// Position in `history` while browsing it with the arrow keys.
+    history_position: Option<usize>,
+    /// Candidates of the last completion, shown under the input.
+    completions: Vec<String>,
+}
+
+impl CommandLineState {
+    fn browse_history(&mut self, older: bool) {
+        let position = match (self.history_position, older) {
+            (None, true) => self.history.len().checked_sub(1),
+            (None, false) => None,
+            (Some(position), true) => Some(position.saturating_sub(1)),
+            (Some(position), false) => Some(position + 1).filter(|&p| p < self.history.len()),
+        };
+        self.history_position = position;
+        self.input = position
+            .map(|position| self.history[position].clone())
+            .unwrap_or_default();
+    }
+
+    fn complete(&mut self, world: &World, add_items: &AddWindowState) {
+        let completions = complete(world, add_items, &self.input);
+        let Some(first) = completions.candidates.first() else {
+            self.completions.clear();
+            return;
+        };
+        // complete up to the first difference between the candidates
+        let mut common = first.as_str();
+        for candidate in &completions.candidates[1..] {
+            let same: usize = first
+                .chars()
+                .zip(candidate.chars())
+                .take_while(|(a, b)| a.eq_ignore_ascii_case(b))
+                .map(|(a, _)| a.len_utf8())
+                .sum();
+            common = &common[..same.min(common.len())];
+        }
+        let command_name = self.input[..completions.start].trim().is_empty();
+        self.input.truncate(completions.start);
+        self.input.push_str(common);
+        if completions.candidates.len() == 1 && command_name {
+            // the argument follows the command name
+            self.input.push(' ');
+        }
+        self.completions = completions.candidates;
+        if self.completions.len() == 1 {
+            self.completions.clear();
+        }
+    }
+}
+
+/// Input line running the console commands, with history and Tab completion.
+///
+/// Returns `true` if the entity selection was changed.
+pub fn command_line_ui(
+    ui: &mut egui::Ui,
+    state: &mut CommandLineState,
+    world: &mut World,
+    selected: &mut SelectedEntities,
+    add_items: &AddWindowState,
+) -> bool {
+    let mut selection_changed = false;
+    ui.horizontal(|ui| {
+        ui.monospace(">");
+        let id = ui.make_persistent_id("console_command_line");
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut state.input)
+                .id(id)
+                .code_editor()
+                .hint_text("Command, Tab to complete, `help` to list them")
+                .desired_width(f32::INFINITY),
+        );
+        if response.changed() {
+            state.completions.clear();
+        }
+
+        if response.lost_focus() && ui.input(|input| input.key_pressed(Key::Enter)) {
+            let line = state.input.trim().to_string();
+            if !line.is_empty() {
+                selection_changed = run_command(world, selected, add_items, &line);
+                if state.history.last() != Some(&line) {
+                    state.history.push(line);
+                }
+            }
+            state.input.clear();
+            state.history_position = None;
+            state.completions.clear();
+            response.request_focus();
+            return;
+        }
+        if !response.has_focus() {
+            return;
+        }
+
+        let key = ui.input_mut(|input| {
+            [Key::Tab, Key::ArrowUp, Key::ArrowDown]
+                .into_iter()
+                .find(|&key| input.consume_key(egui::Modifiers::NONE, key))
+        });
+        match key {
+            Some(Key::Tab) => state.complete(world, add_items),
+            Some(Key::ArrowUp) => state.browse_history(true),
+            Some(Key::ArrowDown) => state.browse_history(false),
+            _ => return,
+        }
+        // put the cursor after the new text
+        if let Some(mut text_state) = egui::TextEdit::load_state(ui.ctx(), id) {
+            let end = egui::text::CCursor::new(state.input.chars().count());
+            text_state.set_ccursor_range(Some(egui::text::CCursorRange::one(end)));
+            egui::TextEdit::store_state(ui.ctx(), id, text_state);
+        }
+    });
+
+    if !state.completions.is_empty() {
+        ui.horizontal_wrapped(|ui| {
+            for candidate in &state.completions {
+                ui.weak(candidate);
+            }
+        });
+    }
+    selection_changed
+}
@@ -0,0 +1,150 @@
+use std::collections::BTreeSet;
+
+use bevy::diagnostic::{
+    Diagnostic, DiagnosticId, DiagnosticsStore, EntityCountDiagnosticsPlugin,
+    FrameTimeDiagnosticsPlugin,
+};
+use bevy::prelude::*;
+use bevy_egui::egui::{self, plot};
+
+use crate::inspector::diagnostics::{DiagnosticsHistory, Series};
+
+/// Diagnostics shown before the others, in this order.
+const FIRST: [DiagnosticId; 3] = [
+    FrameTimeDiagnosticsPlugin::FPS,
+    FrameTimeDiagnosticsPlugin::FRAME_TIME,
+    EntityCountDiagnosticsPlugin::ENTITY_COUNT,
+];
+
+#[derive(Default)]
+pub struct DiagnosticsState {
+    /// Diagnostics shown over the Game view.
+    pub pinned: BTreeSet<DiagnosticId>,
+}
+
+/// Diagnostics of the store, the common ones first and the others by name.
+fn sorted_diagnostics(store: &DiagnosticsStore) -> Vec<&Diagnostic> {
+    let mut diagnostics: Vec<&Diagnostic> = store.iter().collect();
+    diagnostics.sort_by_key(|diagnostic| {
+        let position = FIRST.iter().position(|id| *id == diagnostic.id);
+        (position.unwrap_or(FIRST.len()), diagnostic.name.clone())
+    });
+    diagnostics
+}
+
+pub fn diagnostics_ui(ui: &mut egui::Ui, world: &World, state: &mut DiagnosticsState) {
+    let Some(store) = world.get_resource::<DiagnosticsStore>() else {
+        ui.label("No DiagnosticsStore, add the DiagnosticsPlugin");
+        return;
+    };
+    let history = world.resource::<DiagnosticsHistory>();
+
+    ui.horizontal(|ui| {
+        ui.label(format!("{} entities", world.entities().len()));
+        ui.separator();
+        ui.label(format!("{} archetypes", world.archetypes().len()));
+        ui.separator();
+        ui.label(format!("{} component types", world.components().len()));
+    });
+    ui.separator();
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for diagnostic in sorted_diagnostics(store) {
+            let series = history.get(diagnostic.id);
+            ui.horizontal(|ui| {
+                let mut pinned = state.pinned.contains(&diagnostic.id);
+                if ui
+                    .checkbox(&mut pinned, "")
+                    .on_hover_text("Show over the Game view")
+                    .changed()
+                {
+                    if pinned {
+                        state.pinned.insert(diagnostic.id);
+                    } else {
+                        state.pinned.remove(&diagnostic.id);
+                    }
+                }
+                ui.strong(diagnostic.name.as_ref());
+                ui.label(current_value(diagnostic));
+                if let Some((min, average, max)) = series.and_then(Series::stats) {
+                    ui.weak(format!(
+                        "min {} avg {} max {}",
+                        format_value(min, &diagnostic.suffix),
+                        format_value(average, &diagnostic.suffix),
+                        format_value(max, &diagnostic.suffix),
+                    ));
+                }
+            });
+            if !diagnostic.is_enabled {
+                ui.weak("disabled");
+                continue;
+            }
+            if let Some(series) = series {
+                plot::Plot::new(("diagnostic_plot", diagnostic.id))
+                    .height(80.0)
+                    .show_x(false)
+                    .show_axes([false, true])
+                    .allow_drag(false)
+                    .allow_zoom(false)
+                    .allow_scroll(false)
+                    .include_y(0.0)
+                    .show(ui, |plot_ui| {
+                        plot_ui.line(plot::Line::new(
+                            series.points().collect::<plot::PlotPoints>(),
+                        ))
+                    });
+            }
+            ui.add_space(4.0);
+        }
+    });
+}
+
+/// Compact list of the pinned diagnostics, drawn in the top left corner of `rect`.
+pub fn diagnostics_overlay(
+    ui: &mut egui::Ui,
+    rect: egui::Rect,
+    world: &World,
+    state: &DiagnosticsState,
+) {
+    let Some(store) = world.get_resource::<DiagnosticsStore>() else {
+        return;
+    };
+    let pinned: Vec<&Diagnostic> = sorted_diagnostics(store)
+        .into_iter()
+        .filter(|diagnostic| state.pinned.contains(&diagnostic.id))
+        .collect();
+    if pinned.is_empty() {
+        return;
+    }
+
+    let rect = rect.shrink(8.0);
+    ui.allocate_ui_at_rect(rect, |ui| {
+        egui::Frame::popup(ui.style())
+            .multiply_with_opacity(0.8)
+            .show(ui, |ui| {
+                egui::Grid::new("diagnostics_overlay").show(ui, |ui| {
+                    for diagnostic in pinned {
+                        ui.monospace(diagnostic.name.as_ref());
+                        ui.monospace(current_value(diagnostic));
+                        ui.end_row();
+                    }
+                });
+            });
+    });
+}
+
+/// Smoothed value of a diagnostic, with its unit.
+fn current_value(diagnostic: &Diagnostic) -> String {
+    match diagnostic.smoothed() {
+        Some(value) => format_value(value, &diagnostic.suffix),
+        None => "-".to_string(),
+    }
+}
+
+fn format_value(value: f64, suffix: &str) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e9 {
+        format!("{value}{suffix}")
+    } else {
+        format!("{value:.2}{suffix}")
+    }
+}
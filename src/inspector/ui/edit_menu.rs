@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+use bevy_egui::egui::{self, Key, KeyboardShortcut, Modifiers};
+use bevy_inspector_egui::bevy_inspector::hierarchy::SelectedEntities;
+
+use crate::inspector::undo::UndoHistory;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditAction {
+    Undo,
+    Redo,
+}
+
+impl EditAction {
+    pub const ALL: [EditAction; 2] = [EditAction::Undo, EditAction::Redo];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EditAction::Undo => "Undo",
+            EditAction::Redo => "Redo",
+        }
+    }
+
+    pub fn shortcut(&self) -> KeyboardShortcut {
+        match self {
+            EditAction::Undo => KeyboardShortcut::new(Modifiers::CTRL, Key::Z),
+            EditAction::Redo => KeyboardShortcut::new(Modifiers::CTRL | Modifiers::SHIFT, Key::Z),
+        }
+    }
+
+    /// Returns the action whose shortcut was pressed this frame, consuming it.
+    ///
+    /// Ignored while a text field has the focus, so it keeps its own undo.
+    pub fn from_shortcuts(ctx: &egui::Context) -> Option<Self> {
+        if ctx.memory(|memory| memory.focus().is_some()) {
+            return None;
+        }
+        ctx.input_mut(|input| {
+            Self::ALL
+                .into_iter()
+                .find(|action| input.consume_shortcut(&action.shortcut()))
+        })
+    }
+
+    /// Name of the step this action would revert or apply again.
+    pub fn step_name(&self, world: &World) -> Option<String> {
+        let history = world.resource::<UndoHistory>();
+        match self {
+            EditAction::Undo => history.undo_name(),
+            EditAction::Redo => history.redo_name(),
+        }
+        .map(str::to_string)
+    }
+
+    pub fn run(&self, world: &mut World, selected: &mut SelectedEntities) {
+        let done = match self {
+            EditAction::Undo => UndoHistory::undo(world),
+            EditAction::Redo => UndoHistory::redo(world),
+        };
+        if done {
+            selected.retain(|entity| world.get_entity(entity).is_some());
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use bevy::hierarchy::despawn_with_children_recursive;
+use bevy::prelude::*;
+use bevy_egui::egui::{self, Key, KeyboardShortcut, Modifiers};
+use bevy_egui::EguiClipboard;
+use bevy_inspector_egui::bevy_inspector::guess_entity_name;
+use bevy_inspector_egui::bevy_inspector::hierarchy::SelectedEntities;
+
+use crate::inspector::asset_handles::strengthen_handles;
+use crate::inspector::scene_io::{
+    extract_subtrees, parse_scene, spawn_copy, topmost, with_descendants,
+};
+use crate::inspector::undo::PendingEdit;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntityAction {
+    Rename,
+    Duplicate,
+    Delete,
+    Copy,
+    Paste,
+}
+
+/// Entities being renamed in the Hierarchy, all of them get the edited name.
+#[derive(Clone)]
+pub struct Rename {
+    pub entities: Vec<Entity>,
+    pub name: String,
+    /// `false` until the text field got the focus.
+    pub focused: bool,
+}
+
+impl Rename {
+    pub fn id() -> egui::Id {
+        egui::Id::new("hierarchy_rename")
+    }
+
+    pub fn load(ctx: &egui::Context) -> Option<Rename> {
+        ctx.data(|data| data.get_temp(Self::id()))
+    }
+
+    pub fn store(ctx: &egui::Context, rename: Option<Rename>) {
+        ctx.data_mut(|data| match rename {
+            Some(rename) => data.insert_temp(Self::id(), rename),
+            None => data.remove::<Rename>(Self::id()),
+        });
+    }
+
+    /// Give the edited name to the entities, as one undo step.
+    pub fn apply(self, world: &mut World) {
+        let entities: Vec<Entity> = self
+            .entities
+            .into_iter()
+            .filter(|&entity| world.get_entity(entity).is_some())
+            .collect();
+        let edit = PendingEdit::begin(world, format!("Rename {}", self.name), entities.clone());
+        for &entity in &entities {
+            world
+                .entity_mut(entity)
+                .insert(Name::new(self.name.clone()));
+        }
+        edit.commit(world);
+    }
+}
+
+impl EntityAction {
+    pub const ALL: [EntityAction; 5] = [
+        EntityAction::Rename,
+        EntityAction::Duplicate,
+        EntityAction::Delete,
+        EntityAction::Copy,
+        EntityAction::Paste,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EntityAction::Rename => "Rename",
+            EntityAction::Duplicate => "Duplicate",
+            EntityAction::Delete => "Delete",
+            EntityAction::Copy => "Copy",
+            EntityAction::Paste => "Paste",
+        }
+    }
+
+    pub fn shortcut(&self) -> KeyboardShortcut {
+        match self {
+            EntityAction::Rename => KeyboardShortcut::new(Modifiers::NONE, Key::F2),
+            EntityAction::Duplicate => KeyboardShortcut::new(Modifiers::CTRL, Key::D),
+            EntityAction::Delete => KeyboardShortcut::new(Modifiers::NONE, Key::Delete),
+            EntityAction::Copy => KeyboardShortcut::new(Modifiers::CTRL, Key::C),
+            EntityAction::Paste => KeyboardShortcut::new(Modifiers::CTRL, Key::V),
+        }
+    }
+
+    /// Returns the action whose shortcut was pressed this frame, consuming it.
+    ///
+    /// Ignored while a text field has the focus, so it keeps its own shortcuts.
+    pub fn from_shortcuts(ctx: &egui::Context) -> Option<Self> {
+        if ctx.memory(|memory| memory.focus().is_some()) {
+            return None;
+        }
+        ctx.input_mut(|input| {
+            Self::ALL
+                .into_iter()
+                .find(|action| input.consume_shortcut(&action.shortcut()))
+        })
+    }
+
+    /// Pasting only needs the clipboard, the other actions need selected entities.
+    pub fn enabled(&self, selected: &SelectedEntities) -> bool {
+        *self == EntityAction::Paste || !selected.is_empty()
+    }
+
+    /// Run the action on the selected entities.
+    ///
+    /// Returns `true` if the entity selection was changed.
+    pub fn run(
+        &self,
+        ctx: &egui::Context,
+        world: &mut World,
+        selected: &mut SelectedEntities,
+    ) -> bool {
+        let entities: Vec<Entity> = selected
+            .iter()
+            .filter(|&entity| world.get_entity(entity).is_some())
+            .collect();
+        if entities.is_empty() && *self != EntityAction::Paste {
+            return false;
+        }
+
+        match self {
+            EntityAction::Rename => {
+                let name = guess_entity_name(world, entities[0]);
+                Rename::store(
+                    ctx,
+                    Some(Rename {
+                        entities,
+                        name,
+                        focused: false,
+                    }),
+                );
+                false
+            }
+            EntityAction::Duplicate => {
+                let roots = topmost(world, &entities);
+                let scene = extract_subtrees(world, &roots);
+                let parents: Vec<Entity> = roots
+                    .iter()
+                    .filter_map(|&entity| world.get::<Parent>(entity).map(|parent| parent.get()))
+                    .collect();
+                let copies = paste(world, &scene, "Duplicate", parents, |world, copies| {
+                    // the copies are right after their original
+                    for &(original, copy) in copies {
+                        let Some(parent) = world.get::<Parent>(original).map(Parent::get) else {
+                            continue;
+                        };
+                        let index = world.get::<Children>(parent).and_then(|children| {
+                            children.iter().position(|&child| child == original)
+                        });
+                        let index = index.map_or(0, |index| index + 1);
+                        world.entity_mut(parent).insert_children(index, &[copy]);
+                    }
+                });
+                select_copies(selected, &copies)
+            }
+            EntityAction::Delete => {
+                let roots = topmost(world, &entities);
+                let parents = roots
+                    .iter()
+                    .filter_map(|&entity| world.get::<Parent>(entity).map(|parent| parent.get()));
+                let mut tracked = with_descendants(world, roots.iter().copied());
+                tracked.extend(parents);
+                let name = match roots.as_slice() {
+                    &[entity] => format!("Delete {}", guess_entity_name(world, entity)),
+                    roots => format!("Delete {} entities", roots.len()),
+                };
+                let edit = PendingEdit::begin(world, name, tracked);
+                for &entity in &roots {
+                    despawn_with_children_recursive(world, entity);
+                }
+                edit.commit(world);
+                selected.clear();
+                true
+            }
+            EntityAction::Copy => {
+                let roots = topmost(world, &entities);
+                let scene = extract_subtrees(world, &roots);
+                match scene.serialize_ron(world.resource::<AppTypeRegistry>()) {
+                    Ok(ron) => {
+                        world.resource_mut::<EguiClipboard>().set_contents(&ron);
+                        log::info!("Copied {} entities", scene.entities.len());
+                    }
+                    Err(err) => log::error!("Failed to copy the entities: {err}"),
+                }
+                false
+            }
+            EntityAction::Paste => {
+                let Some(contents) = world.resource::<EguiClipboard>().get_contents() else {
+                    return false;
+                };
+                let scene = match parse_scene(world, contents.as_bytes()) {
+                    Ok(scene) => scene,
+                    Err(err) => {
+                        log::error!("The clipboard doesn't contain entities: {err}");
+                        return false;
+                    }
+                };
+                // pasted next to the selected entity
+                let parent = entities
+                    .first()
+                    .and_then(|&entity| world.get::<Parent>(entity))
+                    .map(|parent| parent.get());
+                let copies = paste(world, &scene, "Paste", parent, |world, copies| {
+                    for &(_, copy) in copies {
+                        if let Some(parent) = parent {
+                            world.entity_mut(copy).set_parent(parent);
+                        }
+                    }
+                });
+                select_copies(selected, &copies)
+            }
+        }
+    }
+}
+
+/// Spawn a copy of `scene` and `place` its roots in the hierarchy, as one undo step.
+///
+/// `parents` are the entities getting new children.
+fn paste(
+    world: &mut World,
+    scene: &DynamicScene,
+    name: &str,
+    parents: impl IntoIterator<Item = Entity>,
+    place: impl FnOnce(&mut World, &[(Entity, Entity)]),
+) -> Vec<(Entity, Entity)> {
+    let mut edit = PendingEdit::begin(world, name, parents);
+    let copies = match spawn_copy(world, scene) {
+        Ok(copies) => copies,
+        Err(err) => {
+            log::error!("Failed to spawn the entities: {err}");
+            return Vec::new();
+        }
+    };
+    edit.set_name(match copies.as_slice() {
+        &[(_, copy)] => format!("{name} {}", guess_entity_name(world, copy)),
+        copies => format!("{name} {} entities", copies.len()),
+    });
+    let spawned = with_descendants(world, copies.iter().map(|(_, copy)| *copy));
+    // the copies must keep their assets alive once the originals are gone
+    strengthen_handles(world, spawned.iter().copied());
+    place(world, &copies);
+    for entity in spawned {
+        edit.track(entity);
+    }
+    edit.commit(world);
+    copies
+}
+
+fn select_copies(selected: &mut SelectedEntities, copies: &[(Entity, Entity)]) -> bool {
+    if copies.is_empty() {
+        return false;
+    }
+    selected.clear();
+    for &(_, copy) in copies {
+        selected.select_maybe_add(copy, true);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::AssetPlugin;
+    use bevy::render::mesh::shape;
+
+    use super::*;
+    use crate::inspector::asset_handles::register_engine_handles;
+    use crate::inspector::undo::UndoHistory;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .add_asset::<Mesh>()
+            .init_resource::<UndoHistory>()
+            .register_type::<Handle<Mesh>>();
+        register_engine_handles(&mut app.world.resource::<AppTypeRegistry>().write());
+        app
+    }
+
+    fn run(app: &mut App, action: EntityAction, entity: Entity) -> Vec<Entity> {
+        let mut selected = SelectedEntities::default();
+        selected.select_replace(entity);
+        action.run(&egui::Context::default(), &mut app.world, &mut selected);
+        selected.iter().collect()
+    }
+
+    #[test]
+    fn duplicate_keeps_the_assets_of_deleted_original() {
+        let mut app = app();
+        let mesh = app
+            .world
+            .resource_mut::<Assets<Mesh>>()
+            .add(shape::Cube::default().into());
+        let original = app.world.spawn(mesh).id();
+
+        let copy = run(&mut app, EntityAction::Duplicate, original)[0];
+        run(&mut app, EntityAction::Delete, original);
+        // the history holds the handles of the original
+        app.world.insert_resource(UndoHistory::default());
+        for _ in 0..3 {
+            app.update();
+        }
+
+        let handle = app.world.get::<Handle<Mesh>>(copy).unwrap();
+        assert!(app.world.resource::<Assets<Mesh>>().get(handle).is_some());
+    }
+
+    #[test]
+    fn undo_delete_keeps_the_assets() {
+        let mut app = app();
+        let mesh = app
+            .world
+            .resource_mut::<Assets<Mesh>>()
+            .add(shape::Cube::default().into());
+        let entity = app.world.spawn(mesh).id();
+
+        run(&mut app, EntityAction::Delete, entity);
+        app.update();
+        assert!(UndoHistory::undo(&mut app.world));
+        app.world.insert_resource(UndoHistory::default());
+        for _ in 0..3 {
+            app.update();
+        }
+
+        let handle = app.world.get::<Handle<Mesh>>(entity).unwrap();
+        assert!(app.world.resource::<Assets<Mesh>>().get(handle).is_some());
+    }
+
+    #[test]
+    fn duplicate_is_named_after_the_entity() {
+        let mut app = app();
+        app.add_plugins(HierarchyPlugin);
+        let parent = app.world.spawn(Name::new("Tree")).id();
+        let child = app.world.spawn(Name::new("Leaf")).id();
+        app.world.entity_mut(parent).add_child(child);
+
+        run(&mut app, EntityAction::Duplicate, parent);
+        let history = app.world.resource::<UndoHistory>();
+        assert_eq!(history.undo_name(), Some("Duplicate Tree"));
+    }
+}
@@ -1,12 +1,15 @@
 use bevy::prelude::*;
 use bevy::render::camera::CameraProjection;
 use bevy_egui::egui;
+use bevy_inspector_egui::bevy_inspector::guess_entity_name;
 use bevy_inspector_egui::bevy_inspector::hierarchy::SelectedEntities;
 use egui_gizmo::{Gizmo, GizmoMode, GizmoOrientation};
+use serde::{Deserialize, Serialize};
 
 use crate::inspector::default_scene::MainSceneCamera;
+use crate::inspector::undo::{EntitiesSnapshot, UndoHistory};
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
 pub struct GizmoSnapValues {
     pub enable: bool,
     pub distance: f32,
@@ -14,6 +17,7 @@ pub struct GizmoSnapValues {
     pub scale: f32,
 }
 
+/// Returns `true` if the transform of the selected entity was changed by the gizmo.
 pub fn draw_gizmo(
     ui: &mut egui::Ui,
     world: &mut World,
@@ -21,7 +25,7 @@ pub fn draw_gizmo(
     gizmo_mode: GizmoMode,
     gizmo_orientation: GizmoOrientation,
     gizmo_snap: GizmoSnapValues,
-) {
+) -> bool {
     let (cam_transform, projection) = world
         .query_filtered::<(&GlobalTransform, &Projection), With<MainSceneCamera>>()
         .single(world);
@@ -29,9 +33,10 @@ pub fn draw_gizmo(
     let projection_matrix = projection.get_projection_matrix();
 
     if selected_entities.len() != 1 {
-        return;
+        return false;
     }
 
+    let mut interacted = false;
     for selected in selected_entities.iter() {
         let Some(transform) = world.get::<Transform>(selected) else {
             continue;
@@ -53,11 +58,24 @@ pub fn draw_gizmo(
             continue;
         };
 
+        let before = EntitiesSnapshot::capture(world, [selected]);
         let mut transform = world.get_mut::<Transform>(selected).unwrap();
         *transform = Transform {
             translation: Vec3::from(<[f32; 3]>::from(result.translation)),
             rotation: Quat::from_array(<[f32; 4]>::from(result.rotation)),
             scale: Vec3::from(<[f32; 3]>::from(result.scale)),
         };
+        let after = EntitiesSnapshot::capture(world, [selected]);
+
+        // every frame of the drag is merged into a single undo step
+        let name = format!("{:?} {}", gizmo_mode, guess_entity_name(world, selected));
+        world.resource_mut::<UndoHistory>().push_merged(
+            egui::Id::new(("gizmo", selected)),
+            name,
+            before,
+            after,
+        );
+        interacted = true;
     }
+    interacted
 }
@@ -1,15 +1,351 @@
-use bevy::ecs::query::ReadOnlyWorldQuery;
+use bevy::ecs::component::ComponentId;
 use bevy::prelude::*;
-use bevy::utils::HashSet;
+use bevy::utils::{HashMap, HashSet};
 use bevy_egui::egui::collapsing_header::paint_default_icon;
-use bevy_egui::egui::{self, CollapsingHeader, RichText};
+use bevy_egui::egui::{self, RichText, Sense};
 use bevy_inspector_egui::bevy_inspector::guess_entity_name;
 use bevy_inspector_egui::bevy_inspector::hierarchy::{SelectedEntities, SelectionMode};
 use bevy_reflect::TypeRegistry;
 
 use crate::inspector::default_scene::InspectorEntity;
+use crate::inspector::hierarchy_tree::HierarchyTree;
+use crate::inspector::undo::PendingEdit;
 
-use super::{add_ui, AddWindowState};
+use super::{add_ui, AddWindowState, EntityAction, Rename};
+
+/// Above this number of changed subtrees, e.g. when a scene is loaded, all the rows are rebuilt
+/// at once.
+const MAX_REFRESHED: usize = 64;
+
+/// State of the Hierarchy: its search, the expanded entities and the rows to display.
+///
+/// The search words are a substring of the entity names, except the `t:Type` ones which require
+/// a component, by short or full type name.
+#[derive(Default)]
+pub struct HierarchyView {
+    pub search: String,
+    expanded: HashSet<Entity>,
+    /// The expanded tree flattened, only the rows in view are drawn.
+    rows: Vec<Row>,
+    /// Index of the row of each entity in `rows`.
+    row_indices: HashMap<Entity, usize>,
+    rows_dirty: bool,
+    /// Entities whose rows must be rebuilt, with the rows of their subtree.
+    refreshed: Vec<Entity>,
+    results: Option<SearchResults>,
+    error: Option<String>,
+    /// Generation of the [`HierarchyTree`] and search the results were built from.
+    built: Option<(u64, String)>,
+    /// Selection whose ancestors were expanded.
+    revealed: Vec<Entity>,
+}
+
+struct Row {
+    entity: Entity,
+    /// Parent of the entity when the row was built.
+    parent: Option<Entity>,
+    depth: usize,
+    has_children: bool,
+}
+
+/// Entities listed by a search, with their ancestors.
+struct SearchResults {
+    matches: HashSet<Entity>,
+    visible: HashSet<Entity>,
+}
+
+impl HierarchyView {
+    /// Resolve the `t:Type` words, `None` if nothing is searched.
+    fn parse(
+        &self,
+        world: &World,
+        type_registry: &TypeRegistry,
+    ) -> Result<Option<(String, Vec<Option<ComponentId>>)>, String> {
+        let mut name = Vec::new();
+        let mut components = Vec::new();
+        for word in self.search.split_whitespace() {
+            let Some(type_name) = word.strip_prefix("t:") else {
+                name.push(word);
+                continue;
+            };
+            let registration = type_registry
+                .get_with_short_name(type_name)
+                .or_else(|| type_registry.get_with_name(type_name))
+                .ok_or_else(|| format!("Unknown or ambiguous type {type_name}"))?;
+            if registration.data::<ReflectComponent>().is_none() {
+                return Err(format!("{type_name} is not a component"));
+            }
+            // a component never inserted has no id yet, and no entity has it
+            components.push(world.components().get_id(registration.type_id()));
+        }
+        if name.is_empty() && components.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some((name.join(" ").to_lowercase(), components)))
+    }
+
+    fn results(world: &World, name: &str, components: &[Option<ComponentId>]) -> SearchResults {
+        let matches: HashSet<Entity> = world
+            .iter_entities()
+            .map(|entity| entity.id())
+            .filter(|&entity| Self::is_match(world, entity, name, components))
+            .collect();
+        let visible = Self::with_ancestors(world, &matches);
+        SearchResults { matches, visible }
+    }
+
+    fn is_match(
+        world: &World,
+        entity: Entity,
+        name: &str,
+        components: &[Option<ComponentId>],
+    ) -> bool {
+        let Some(entity_ref) = world.get_entity(entity) else {
+            return false;
+        };
+        !entity_ref.contains::<InspectorEntity>()
+            && components
+                .iter()
+                .all(|id| id.is_some_and(|id| entity_ref.contains_id(id)))
+            && (name.is_empty()
+                || guess_entity_name(world, entity)
+                    .to_lowercase()
+                    .contains(name))
+    }
+
+    fn with_ancestors(world: &World, matches: &HashSet<Entity>) -> HashSet<Entity> {
+        matches
+            .iter()
+            .flat_map(|&entity| {
+                std::iter::successors(Some(entity), |&entity| {
+                    world.get::<Parent>(entity).map(|parent| parent.get())
+                })
+            })
+            .collect()
+    }
+
+    fn is_visible(&self, entity: Entity) -> bool {
+        self.results
+            .iter()
+            .all(|results| results.visible.contains(&entity))
+    }
+
+    /// Expand the ancestors of the entities.
+    fn reveal(&mut self, world: &World, entities: impl IntoIterator<Item = Entity>) {
+        for entity in entities {
+            let ancestors = std::iter::successors(world.get::<Parent>(entity), |parent| {
+                world.get::<Parent>(parent.get())
+            });
+            self.expanded.extend(ancestors.map(|parent| parent.get()));
+        }
+        self.rows_dirty = true;
+    }
+
+    /// Rebuild the search results and the rows if the hierarchy, the search, the selection or
+    /// the expanded entities changed.
+    ///
+    /// Only the subtrees changed in the last generation of the [`HierarchyTree`] are rebuilt,
+    /// everything is if the Hierarchy wasn't drawn during several generations.
+    fn update(&mut self, world: &World, type_registry: &TypeRegistry, selected: &SelectedEntities) {
+        let generation = world.resource::<HierarchyTree>().generation();
+        let built = self.built.as_ref();
+        let search_changed = built.map(|(_, search)| search) != Some(&self.search);
+        let built_generation = built.map(|(generation, _)| *generation);
+        if !search_changed && built_generation.is_some_and(|built| built + 1 == generation) {
+            self.update_changed(world, type_registry);
+            self.rows_dirty |= self.refreshed.len() > MAX_REFRESHED;
+            self.built = Some((generation, self.search.clone()));
+        } else if search_changed || built_generation != Some(generation) {
+            self.expanded
+                .retain(|&entity| world.get_entity(entity).is_some());
+            (self.results, self.error) = match self.parse(world, type_registry) {
+                Ok(search) => {
+                    let results =
+                        search.map(|(name, components)| Self::results(world, &name, &components));
+                    (results, None)
+                }
+                Err(err) => (None, Some(err)),
+            };
+            if search_changed {
+                let matches: Vec<Entity> = self
+                    .results
+                    .iter()
+                    .flat_map(|results| results.matches.iter().copied())
+                    .collect();
+                self.reveal(world, matches);
+            }
+            self.built = Some((generation, self.search.clone()));
+            self.rows_dirty = true;
+        }
+
+        let selection: Vec<Entity> = selected.iter().collect();
+        if selection != self.revealed {
+            self.reveal(world, selection.iter().copied());
+            self.revealed = selection;
+        }
+
+        if self.rows_dirty {
+            self.rebuild_rows(world);
+            self.rows_dirty = false;
+            self.refreshed.clear();
+        }
+        for entity in std::mem::take(&mut self.refreshed) {
+            self.refresh_rows(world, entity);
+        }
+    }
+
+    /// Update the search results of the entities changed in the last generation, and queue
+    /// their rows to be rebuilt.
+    fn update_changed(&mut self, world: &World, type_registry: &TypeRegistry) {
+        let tree = world.resource::<HierarchyTree>();
+        let mut changed: HashSet<Entity> = tree.moved().iter().copied().collect();
+        self.expanded
+            .retain(|&entity| world.get_entity(entity).is_some());
+
+        let search = self.parse(world, type_registry);
+        let (Some(results), Ok(Some((name, components)))) = (&mut self.results, search) else {
+            self.refreshed.extend(changed);
+            return;
+        };
+        // a match only depends on the entity, and every spawned or despawned entity was moved
+        changed.extend(tree.renamed());
+        for &entity in &changed {
+            if Self::is_match(world, entity, &name, &components) {
+                results.matches.insert(entity);
+            } else {
+                results.matches.remove(&entity);
+            }
+        }
+        let visible = Self::with_ancestors(world, &results.matches);
+        // the rows to rebuild start at the first ancestor still visible or hidden
+        for entity in changed {
+            let mut ancestors = std::iter::successors(Some(entity), |&entity| {
+                world.get::<Parent>(entity).map(|parent| parent.get())
+            });
+            let unchanged = ancestors
+                .find(|ancestor| results.visible.contains(ancestor) == visible.contains(ancestor));
+            self.refreshed.push(unchanged.unwrap_or(entity));
+        }
+        results.visible = visible;
+    }
+
+    /// Index of the row of `entity`, `None` if it isn't displayed. The row is removed with its
+    /// subtree if the entity was moved or despawned.
+    fn row_index(&mut self, world: &World, entity: Entity) -> Option<usize> {
+        let index = *self.row_indices.get(&entity)?;
+        let parent = world.get::<Parent>(entity).map(|parent| parent.get());
+        let is_root = world.resource::<HierarchyTree>().is_root(entity);
+        if self.rows[index].parent == parent && (parent.is_some() || is_root) {
+            return Some(index);
+        }
+        let end = self.subtree_end(index);
+        self.splice_rows(index..end, Vec::new());
+        None
+    }
+
+    /// Replace the rows in `range`, and update the indices of the rows after it.
+    fn splice_rows(&mut self, range: std::ops::Range<usize>, rows: Vec<Row>) {
+        let start = range.start;
+        for row in &self.rows[range.clone()] {
+            // a moved entity may still have a row under its previous parent
+            let index = self.row_indices.get(&row.entity);
+            if index.is_some_and(|index| range.contains(index)) {
+                self.row_indices.remove(&row.entity);
+            }
+        }
+        self.rows.splice(range, rows);
+        for (index, row) in self.rows.iter().enumerate().skip(start) {
+            self.row_indices.insert(row.entity, index);
+        }
+    }
+
+    /// End of the rows of the subtree starting at `index`.
+    fn subtree_end(&self, index: usize) -> usize {
+        let depth = self.rows[index].depth;
+        self.rows[index + 1..]
+            .iter()
+            .position(|row| row.depth <= depth)
+            .map_or(self.rows.len(), |end| index + 1 + end)
+    }
+
+    /// Rebuild the rows of the closest displayed entity among `entity` and its ancestors, or
+    /// insert the root of `entity` if none is displayed.
+    fn refresh_rows(&mut self, world: &World, entity: Entity) {
+        let mut top = entity;
+        let ancestors = std::iter::successors(Some(entity), |&entity| {
+            world.get::<Parent>(entity).map(|parent| parent.get())
+        });
+        for ancestor in ancestors {
+            if let Some(index) = self.row_index(world, ancestor) {
+                let end = self.subtree_end(index);
+                let row = &self.rows[index];
+                let rows = self.subtree_rows(world, ancestor, row.parent, row.depth);
+                self.splice_rows(index..end, rows);
+                return;
+            }
+            top = ancestor;
+        }
+
+        // a new root, or a root shown again by the search
+        if !world.resource::<HierarchyTree>().is_root(top) {
+            return;
+        }
+        let index = self
+            .rows
+            .iter()
+            .position(|row| row.depth == 0 && row.entity > top)
+            .unwrap_or(self.rows.len());
+        let rows = self.subtree_rows(world, top, None, 0);
+        self.splice_rows(index..index, rows);
+    }
+
+    fn rebuild_rows(&mut self, world: &World) {
+        let roots = world.resource::<HierarchyTree>().roots();
+        let rows = roots
+            .iter()
+            .flat_map(|&root| self.subtree_rows(world, root, None, 0))
+            .collect();
+        self.row_indices.clear();
+        self.splice_rows(0..self.rows.len(), rows);
+    }
+
+    /// The rows of `entity` and its expanded descendants, none if it is despawned or hidden by
+    /// the search.
+    fn subtree_rows(
+        &self,
+        world: &World,
+        entity: Entity,
+        parent: Option<Entity>,
+        depth: usize,
+    ) -> Vec<Row> {
+        let mut rows = Vec::new();
+        if world.get_entity(entity).is_none() || !self.is_visible(entity) {
+            return rows;
+        }
+        // depth first, the children of an expanded entity right after it
+        let mut stack = vec![(entity, parent, depth)];
+        while let Some((entity, parent, depth)) = stack.pop() {
+            let children: Vec<Entity> = world
+                .get::<Children>(entity)
+                .map(|children| {
+                    let children = children.iter().copied();
+                    children.filter(|&child| self.is_visible(child)).collect()
+                })
+                .unwrap_or_default();
+            rows.push(Row {
+                entity,
+                parent,
+                depth,
+                has_children: !children.is_empty(),
+            });
+            if self.expanded.contains(&entity) {
+                let children = children.into_iter().rev();
+                stack.extend(children.map(|child| (child, Some(entity), depth + 1)));
+            }
+        }
+        rows
+    }
+}
 
 /// Display UI of the entity hierarchy.
 ///
@@ -19,10 +355,35 @@ pub fn hierarchy_ui(
     ui: &mut egui::Ui,
     selected: &mut SelectedEntities,
     state: &AddWindowState,
+    view: &mut HierarchyView,
 ) -> bool {
     let type_registry = world.resource::<AppTypeRegistry>().clone();
     let type_registry = type_registry.read();
 
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut view.search)
+                .hint_text("Search, t:Type for a component")
+                .desired_width(ui.available_width() - 24.0),
+        );
+        if ui
+            .add_enabled(!view.search.is_empty(), egui::Button::new("✖"))
+            .on_hover_text("Clear the search")
+            .clicked()
+        {
+            view.search.clear();
+        }
+    });
+    view.update(world, &type_registry, selected);
+    if let Some(err) = &view.error {
+        ui.colored_label(ui.visuals().error_fg_color, err);
+        return false;
+    }
+    if let Some(results) = &view.results {
+        ui.weak(format!("{} matches", results.matches.len()));
+    }
+    ui.separator();
+
     Hierarchy {
         world,
         type_registry: &type_registry,
@@ -30,8 +391,124 @@ pub fn hierarchy_ui(
         context_menu: None,
         shortcircuit_entity: None,
         extra_state: state,
+        dragged: Vec::new(),
+        drop_target: None,
+        pointer_on_row: false,
+        view,
     }
-    .show::<()>(ui)
+    .show(ui)
+}
+
+/// Where the dragged entities are dropped in the hierarchy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DropTarget {
+    /// After the children of the entity.
+    Onto(Entity),
+    Before(Entity),
+    After(Entity),
+    /// Without parent.
+    Root,
+}
+
+fn is_ancestor(world: &World, ancestor: Entity, entity: Entity) -> bool {
+    std::iter::successors(Some(entity), |&entity| {
+        world.get::<Parent>(entity).map(|parent| parent.get())
+    })
+    .any(|entity| entity == ancestor)
+}
+
+/// Move the `dragged` entities to `target` as one undo step, keeping their global transform
+/// unless `keep_local`.
+fn reparent(world: &mut World, dragged: &[Entity], target: DropTarget, keep_local: bool) {
+    let (parent, sibling) = match target {
+        DropTarget::Onto(entity) => (Some(entity), None),
+        DropTarget::Before(entity) | DropTarget::After(entity) => (
+            world.get::<Parent>(entity).map(|parent| parent.get()),
+            Some(entity),
+        ),
+        DropTarget::Root => (None, None),
+    };
+    // the descendants of a dragged entity move with it, and an entity can't be its own parent
+    let moved: Vec<Entity> = dragged
+        .iter()
+        .copied()
+        .filter(|&entity| world.get_entity(entity).is_some() && Some(entity) != sibling)
+        .filter(|&entity| {
+            !dragged
+                .iter()
+                .any(|&other| other != entity && is_ancestor(world, other, entity))
+        })
+        .filter(|&entity| !parent.is_some_and(|parent| is_ancestor(world, entity, parent)))
+        .collect();
+    if moved.is_empty() {
+        return;
+    }
+
+    let name = match moved.as_slice() {
+        &[entity] => format!("Reparent {}", guess_entity_name(world, entity)),
+        entities => format!("Reparent {} entities", entities.len()),
+    };
+    let old_parents: Vec<Entity> = moved
+        .iter()
+        .filter_map(|&entity| world.get::<Parent>(entity).map(|parent| parent.get()))
+        .collect();
+    let edit = PendingEdit::begin(
+        world,
+        name,
+        moved.iter().copied().chain(old_parents).chain(parent),
+    );
+
+    let transforms: Vec<Option<Transform>> = if keep_local {
+        vec![None; moved.len()]
+    } else {
+        let parent_transform = parent
+            .and_then(|parent| world.get::<GlobalTransform>(parent))
+            .copied()
+            .unwrap_or_default();
+        moved
+            .iter()
+            .map(|&entity| {
+                let transform = world.get::<GlobalTransform>(entity)?;
+                Some(transform.reparented_to(&parent_transform))
+            })
+            .collect()
+    };
+
+    match parent {
+        Some(parent) => {
+            let siblings: Vec<Entity> = world
+                .get::<Children>(parent)
+                .map(|children| {
+                    let children = children.iter().copied();
+                    children.filter(|child| !moved.contains(child)).collect()
+                })
+                .unwrap_or_default();
+            let position = |sibling| siblings.iter().position(|&child| child == sibling);
+            let index = match target {
+                DropTarget::Before(sibling) => position(sibling),
+                DropTarget::After(sibling) => position(sibling).map(|index| index + 1),
+                _ => None,
+            };
+            world
+                .entity_mut(parent)
+                .insert_children(index.unwrap_or(siblings.len()), &moved);
+        }
+        None => {
+            for &entity in &moved {
+                world.entity_mut(entity).remove_parent();
+            }
+        }
+    }
+
+    for (&entity, transform) in moved.iter().zip(transforms) {
+        let Some(transform) = transform else {
+            continue;
+        };
+        if let Some(mut current) = world.get_mut::<Transform>(entity) {
+            *current = transform;
+        }
+    }
+    edit.commit(world);
 }
 
 pub struct Hierarchy<'a> {
@@ -42,74 +519,162 @@ pub struct Hierarchy<'a> {
     pub shortcircuit_entity:
         Option<&'a mut dyn FnMut(&mut egui::Ui, Entity, &mut World, &AddWindowState) -> bool>,
     pub extra_state: &'a AddWindowState<'a>,
+    /// Entities being dragged, to be reparented where they are dropped.
+    dragged: Vec<Entity>,
+    drop_target: Option<DropTarget>,
+    /// `true` if the dragged entities are over an entity, otherwise they are over empty space.
+    pointer_on_row: bool,
+    view: &'a mut HierarchyView,
 }
 
 impl Hierarchy<'_> {
-    pub fn show<F: ReadOnlyWorldQuery>(&mut self, ui: &mut egui::Ui) -> bool {
-        let mut root_query = self
-            .world
-            .query_filtered::<Entity, (Without<Parent>, Without<InspectorEntity>, F)>();
-
-        let always_open: HashSet<Entity> = self
-            .selected
-            .iter()
-            .flat_map(|selected| {
-                std::iter::successors(Some(selected), |&entity| {
-                    self.world.get::<Parent>(entity).map(|parent| parent.get())
-                })
-                .skip(1)
-            })
-            .collect();
-
-        let mut entities: Vec<_> = root_query.iter(self.world).collect();
-        entities.sort();
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let drag_id = ui.make_persistent_id("hierarchy_drag");
+        self.dragged = ui
+            .memory(|memory| memory.data.get_temp::<Vec<Entity>>(drag_id))
+            .unwrap_or_default();
 
         let mut selected = false;
-        ui.vertical(|ui| {
-            for &entity in &entities {
-                selected |= self.entity_ui(ui, entity, &always_open, &entities);
-            }
-        })
-        .response
-        .context_menu(|ui| {
-            add_ui(ui, self.extra_state).map(|add_item| {
-                selected |= true;
-                let entity = self.world.spawn_empty().id();
-                add_item.add_to_entity(self.world, entity);
-                self.selected.select_replace(entity);
+        // only the rows in view are drawn, they all have the same height
+        let rows = std::mem::take(&mut self.view.rows);
+        let row_height = ui.spacing().interact_size.y;
+        let output = egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show_rows(ui, row_height, rows.len(), |ui, range| {
+                for row in &rows[range] {
+                    selected |= self.row_ui(ui, row, row_height);
+                }
             });
-        });
+        self.view.rows = rows;
+
+        let background_id = ui.make_persistent_id("hierarchy_background");
+        ui.interact(output.inner_rect, background_id, Sense::click())
+            .context_menu(|ui| {
+                add_ui(ui, self.extra_state).map(|add_item| {
+                    selected |= true;
+                    let mut edit =
+                        PendingEdit::begin(self.world, format!("Add {}", add_item.name()), []);
+                    let entity = self.world.spawn_empty().id();
+                    add_item.add_to_entity(self.world, entity);
+                    edit.track(entity);
+                    edit.commit(self.world);
+                    self.selected.select_replace(entity);
+                });
+                ui.separator();
+                let paste = EntityAction::Paste;
+                let button = egui::Button::new(paste.label())
+                    .shortcut_text(ui.ctx().format_shortcut(&paste.shortcut()));
+                if ui.add(button).clicked() {
+                    // at the root, not next to the selection
+                    self.selected.clear();
+                    selected |= paste.run(ui.ctx(), self.world, self.selected);
+                    ui.close_menu();
+                }
+            });
+
+        if !self.dragged.is_empty() {
+            self.drop_dragged(ui);
+        }
+        ui.memory_mut(|memory| memory.data.insert_temp(drag_id, self.dragged.clone()));
         selected
     }
 
-    fn entity_ui(
-        &mut self,
-        ui: &mut egui::Ui,
-        entity: Entity,
-        always_open: &HashSet<Entity>,
-        at_same_level: &[Entity],
-    ) -> bool {
-        let mut new_selection = false;
-        let selected = self.selected.contains(entity);
+    /// Reparent the dragged entities once they are dropped, on the empty space they are
+    /// unparented. The global transforms are kept, unless Alt is held.
+    fn drop_dragged(&mut self, ui: &mut egui::Ui) {
+        let (dragging, released, pointer, keep_local) = ui.input(|input| {
+            (
+                input.pointer.is_decidedly_dragging(),
+                input.pointer.any_released(),
+                input.pointer.hover_pos(),
+                input.modifiers.alt,
+            )
+        });
+        if !dragging {
+            if released || !ui.input(|input| input.pointer.any_down()) {
+                self.dragged.clear();
+            }
+            return;
+        }
 
-        let entity_name = guess_entity_name(self.world, entity);
-        let mut name = RichText::new(entity_name);
-        if selected {
-            name = name.strong();
+        let on_panel = pointer.is_some_and(|pointer| ui.clip_rect().contains(pointer));
+        if self.drop_target.is_none() && !self.pointer_on_row && on_panel {
+            self.drop_target = Some(DropTarget::Root);
+            let stroke = ui.visuals().selection.stroke;
+            ui.painter()
+                .rect_stroke(ui.clip_rect().shrink(1.0), 2.0, stroke);
         }
+        ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
+        let text = match self.dragged.len() {
+            1 => "1 entity".to_string(),
+            count => format!("{count} entities"),
+        };
+        egui::show_tooltip_at_pointer(ui.ctx(), egui::Id::new("hierarchy_drag"), |ui| {
+            ui.label(text);
+            ui.weak("Hold Alt to keep the local transforms");
+        });
 
-        let has_children = self
-            .world
-            .get::<Children>(entity)
-            .map_or(false, |children| children.len() > 0);
+        if released {
+            if let Some(target) = self.drop_target {
+                reparent(self.world, &self.dragged, target, keep_local);
+            }
+            self.dragged.clear();
+        }
+    }
+
+    /// Show where the dragged entities would be dropped if the pointer is over `entity`.
+    fn hover_drop_target(&mut self, ui: &egui::Ui, entity: Entity, rect: egui::Rect) {
+        let dragging = ui.input(|input| input.pointer.is_decidedly_dragging());
+        let pointer = ui.input(|input| input.pointer.hover_pos());
+        let Some(pointer) = pointer.filter(|pointer| dragging && rect.contains(*pointer)) else {
+            return;
+        };
+        self.pointer_on_row = true;
+        if self.dragged.contains(&entity) {
+            return;
+        }
 
-        let open = if !has_children {
-            Some(false)
-        } else if always_open.contains(&entity) {
-            Some(true)
+        // the edges of the row insert between the siblings, its middle adds a child
+        let edge = rect.height() / 4.0;
+        let stroke = ui.visuals().selection.stroke;
+        let painter = ui.painter();
+        let target = if pointer.y < rect.top() + edge {
+            painter.hline(rect.x_range(), rect.top(), stroke);
+            DropTarget::Before(entity)
+        } else if pointer.y > rect.bottom() - edge {
+            painter.hline(rect.x_range(), rect.bottom(), stroke);
+            DropTarget::After(entity)
         } else {
-            None
+            painter.rect_stroke(rect, 2.0, stroke);
+            DropTarget::Onto(entity)
         };
+        self.drop_target = Some(target);
+    }
+
+    /// The visible entities with the same parent as `entity`, in order.
+    fn siblings(&self, entity: Entity) -> Vec<Entity> {
+        let parent = self.world.get::<Parent>(entity);
+        let siblings = match parent.and_then(|parent| self.world.get::<Children>(parent.get())) {
+            Some(children) => children.to_vec(),
+            None => self.world.resource::<HierarchyTree>().roots().to_vec(),
+        };
+        siblings
+            .into_iter()
+            .filter(|&sibling| self.view.is_visible(sibling))
+            .collect()
+    }
+
+    /// One row of the flattened tree, returns `true` if the entity selection was changed.
+    fn row_ui(&mut self, ui: &mut egui::Ui, row: &Row, row_height: f32) -> bool {
+        let entity = row.entity;
+        let size = egui::vec2(ui.available_width(), row_height);
+        let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+        // despawned since the rows were built
+        if self.world.get_entity(entity).is_none() {
+            return false;
+        }
+        let mut ui = ui.child_ui(rect, egui::Layout::left_to_right(egui::Align::Center));
+        let ui = &mut ui;
 
         if let Some(shortcircuit_entity) = self.shortcircuit_entity.as_mut() {
             if shortcircuit_entity(ui, entity, self.world, self.extra_state) {
@@ -117,35 +682,64 @@ impl Hierarchy<'_> {
             }
         }
 
-        #[allow(deprecated)] // the suggested replacement doesn't really work
-        let response = CollapsingHeader::new(name)
-            .id_source(entity)
-            .icon(move |ui, openness, response| {
-                if !has_children {
-                    return;
-                }
-                paint_default_icon(ui, openness, response);
-            })
-            .selectable(true)
-            .selected(selected)
-            .open(open)
-            .show(ui, |ui| {
-                let children = self.world.get::<Children>(entity);
-                if let Some(children) = children {
-                    let children = children.to_vec();
-                    for &child in children.iter() {
-                        self.entity_ui(ui, child, always_open, &children);
-                    }
+        ui.add_space(ui.spacing().indent * row.depth as f32);
+        let icon_size = egui::Vec2::splat(ui.spacing().icon_width);
+        let (_, toggle) = ui.allocate_exact_size(icon_size, Sense::click());
+        if row.has_children {
+            let expanded = self.view.expanded.contains(&entity);
+            paint_default_icon(ui, if expanded { 1.0 } else { 0.0 }, &toggle);
+            if toggle.clicked() {
+                if expanded {
+                    self.view.expanded.remove(&entity);
                 } else {
-                    ui.label("No children");
+                    self.view.expanded.insert(entity);
                 }
-            });
-        let header_response = response.header_response;
+                self.view.refreshed.push(entity);
+            }
+        }
+
+        let rename = Rename::load(ui.ctx());
+        if let Some(rename) = rename.filter(|rename| rename.entities.first() == Some(&entity)) {
+            self.rename_ui(ui, rename);
+            return false;
+        }
+
+        let mut new_selection = false;
+        let selected = self.selected.contains(entity);
+
+        let entity_name = guess_entity_name(self.world, entity);
+        let mut name = RichText::new(entity_name);
+        if selected {
+            name = name.strong();
+        } else if self
+            .view
+            .results
+            .as_ref()
+            .is_some_and(|results| !results.matches.contains(&entity))
+        {
+            // an ancestor of the search results
+            name = name.weak();
+        }
+
+        let header_response = ui.add(egui::SelectableLabel::new(selected, name));
+
+        let drag_response = header_response.interact(Sense::drag());
+        if drag_response.drag_started() {
+            self.dragged = if selected {
+                self.selected.iter().collect()
+            } else {
+                vec![entity]
+            };
+        }
+        if !self.dragged.is_empty() {
+            self.hover_drop_target(ui, entity, rect);
+        }
 
         if header_response.clicked() {
             let selection_mode = ui.input(|input| {
                 SelectionMode::from_ctrl_shift(input.modifiers.ctrl, input.modifiers.shift)
             });
+            let at_same_level = self.siblings(entity);
             let extend_with = |from, to| {
                 // PERF: this could be done in one scan
                 let from_position = at_same_level.iter().position(|&entity| entity == from);
@@ -163,16 +757,64 @@ impl Hierarchy<'_> {
             new_selection = true;
         }
 
+        // the actions apply to the selection, which must contain the clicked entity
+        if header_response.secondary_clicked() && !selected {
+            self.selected.select_replace(entity);
+            new_selection = true;
+        }
+
         // if let Some(context_menu) = self.context_menu.as_mut() {
         header_response.context_menu(|ui| {
             if let Some(add_item) = add_ui(ui, self.extra_state) {
-                let entity = self.world.spawn_empty().set_parent(entity).id();
-                add_item.add_to_entity(self.world, entity);
+                let mut edit =
+                    PendingEdit::begin(self.world, format!("Add {}", add_item.name()), [entity]);
+                let child = self.world.spawn_empty().set_parent(entity).id();
+                add_item.add_to_entity(self.world, child);
+                edit.track(child);
+                edit.commit(self.world);
+                self.view.expanded.insert(entity);
             }
+            ui.separator();
+            new_selection |= self.entity_actions_ui(ui);
             // (context_menu)(ui, entity, self.world, self.extra_state)
         });
         // }
 
         new_selection
     }
+
+    /// Buttons of the [`EntityAction`]s, returns `true` if the entity selection was changed.
+    fn entity_actions_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut new_selection = false;
+        for action in EntityAction::ALL {
+            let button = egui::Button::new(action.label())
+                .shortcut_text(ui.ctx().format_shortcut(&action.shortcut()));
+            if ui
+                .add_enabled(action.enabled(self.selected), button)
+                .clicked()
+            {
+                new_selection |= action.run(ui.ctx(), self.world, self.selected);
+                ui.close_menu();
+            }
+        }
+        new_selection
+    }
+
+    /// Text field editing the `Name` of the renamed entities, applied with Enter or by clicking
+    /// elsewhere and cancelled with Escape.
+    fn rename_ui(&mut self, ui: &mut egui::Ui, mut rename: Rename) {
+        let response = ui
+            .add(egui::TextEdit::singleline(&mut rename.name).desired_width(ui.available_width()));
+        if !rename.focused {
+            response.request_focus();
+            rename.focused = true;
+        } else if response.lost_focus() {
+            if !ui.input(|input| input.key_pressed(egui::Key::Escape)) {
+                rename.apply(self.world);
+            }
+            Rename::store(ui.ctx(), None);
+            return;
+        }
+        Rename::store(ui.ctx(), Some(rename));
+    }
 }
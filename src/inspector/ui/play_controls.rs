@@ -0,0 +1,113 @@
+use bevy::prelude::*;
+use bevy_egui::egui::{self, Key, KeyboardShortcut, Modifiers};
+use bevy_inspector_egui::bevy_inspector::hierarchy::SelectedEntities;
+
+use crate::inspector::play_mode::{PlayMode, PlayState};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayAction {
+    Play,
+    Pause,
+    Step,
+    Stop,
+}
+
+impl PlayAction {
+    pub const ALL: [PlayAction; 4] = [
+        PlayAction::Play,
+        PlayAction::Pause,
+        PlayAction::Step,
+        PlayAction::Stop,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PlayAction::Play => "Play",
+            PlayAction::Pause => "Pause",
+            PlayAction::Step => "Step",
+            PlayAction::Stop => "Stop",
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            PlayAction::Play => "▶",
+            PlayAction::Pause => "⏸",
+            PlayAction::Step => "⏭",
+            PlayAction::Stop => "⏹",
+        }
+    }
+
+    pub fn shortcut(&self) -> KeyboardShortcut {
+        match self {
+            PlayAction::Play => KeyboardShortcut::new(Modifiers::NONE, Key::F5),
+            PlayAction::Pause => KeyboardShortcut::new(Modifiers::NONE, Key::F6),
+            PlayAction::Step => KeyboardShortcut::new(Modifiers::NONE, Key::F10),
+            PlayAction::Stop => KeyboardShortcut::new(Modifiers::SHIFT, Key::F5),
+        }
+    }
+
+    /// Returns the action whose shortcut was pressed this frame, consuming it.
+    pub fn from_shortcuts(ctx: &egui::Context) -> Option<Self> {
+        ctx.input_mut(|input| {
+            Self::ALL
+                .into_iter()
+                .find(|action| input.consume_shortcut(&action.shortcut()))
+        })
+    }
+
+    pub fn enabled(&self, state: PlayState) -> bool {
+        match self {
+            PlayAction::Play => state != PlayState::Playing,
+            PlayAction::Pause => state == PlayState::Playing,
+            PlayAction::Step => true,
+            PlayAction::Stop => state != PlayState::Stopped,
+        }
+    }
+
+    /// `true` if the button of this action is shown as pressed.
+    fn active(&self, state: PlayState) -> bool {
+        matches!(
+            (self, state),
+            (PlayAction::Play, PlayState::Playing) | (PlayAction::Pause, PlayState::Paused)
+        )
+    }
+
+    pub fn run(&self, world: &mut World, selected: &mut SelectedEntities) {
+        match self {
+            PlayAction::Play => PlayMode::play(world),
+            PlayAction::Pause => PlayMode::pause(world),
+            PlayAction::Step => PlayMode::step(world),
+            PlayAction::Stop => {
+                PlayMode::stop(world);
+                selected.retain(|entity| world.get_entity(entity).is_some());
+            }
+        }
+    }
+}
+
+pub fn play_controls_ui(ui: &mut egui::Ui, world: &mut World, selected: &mut SelectedEntities) {
+    let state = world.resource::<PlayMode>().state();
+    ui.style_mut().visuals.button_frame = false;
+
+    for action in PlayAction::ALL {
+        let shortcut = ui.ctx().format_shortcut(&action.shortcut());
+        if ui
+            .add_enabled(
+                action.enabled(state),
+                egui::Button::new(action.icon())
+                    .fill(if action.active(state) {
+                        egui::Color32::DARK_GRAY
+                    } else {
+                        egui::Color32::TRANSPARENT
+                    })
+                    .min_size(egui::Vec2::splat(18.0))
+                    .rounding(egui::Rounding::none()),
+            )
+            .on_hover_text(format!("{} ({shortcut})", action.label()))
+            .clicked()
+        {
+            action.run(world, selected);
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_egui::egui::{self, Align, Color32, Layout, Sense};
+use egui_extras::{Column, TableBuilder};
+
+use crate::inspector::profiler::{ProfiledFrame, Profiler, SpanKind, PROFILED_FRAMES};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProfilerView {
+    Table,
+    Flame,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProfilerColumn {
+    Schedule,
+    System,
+    Mean,
+    Max,
+    Last,
+}
+
+impl ProfilerColumn {
+    pub const ALL: [ProfilerColumn; 5] = [
+        ProfilerColumn::Schedule,
+        ProfilerColumn::System,
+        ProfilerColumn::Mean,
+        ProfilerColumn::Max,
+        ProfilerColumn::Last,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProfilerColumn::Schedule => "Schedule",
+            ProfilerColumn::System => "System",
+            ProfilerColumn::Mean => "Mean",
+            ProfilerColumn::Max => "Max",
+            ProfilerColumn::Last => "Last",
+        }
+    }
+}
+
+pub struct ProfilerState {
+    view: ProfilerView,
+    sort: ProfilerColumn,
+    descending: bool,
+    /// Frame shown by the flame view, counted from the newest one.
+    flame_frame: usize,
+    record_frames: usize,
+    record_path: String,
+}
+
+impl Default for ProfilerState {
+    fn default() -> Self {
+        Self {
+            view: ProfilerView::Table,
+            sort: ProfilerColumn::Mean,
+            descending: true,
+            flame_frame: 0,
+            record_frames: 300,
+            record_path: "trace.json".to_string(),
+        }
+    }
+}
+
+/// Durations of a system over the kept frames.
+struct SystemStats {
+    schedule: Arc<str>,
+    name: Arc<str>,
+    /// Average time spent per frame, including the frames where it didn't run.
+    mean: Duration,
+    /// Longest single run.
+    max: Duration,
+    /// Time spent during the newest frame.
+    last: Duration,
+}
+
+fn system_stats(frames: &VecDeque<ProfiledFrame>) -> Vec<SystemStats> {
+    let mut totals: BTreeMap<(Arc<str>, Arc<str>), SystemStats> = BTreeMap::new();
+    let newest = frames.back().map(|frame| frame.index);
+    for frame in frames {
+        for span in frame
+            .spans
+            .iter()
+            .filter(|span| span.kind == SpanKind::System)
+        {
+            let stats = totals
+                .entry((span.schedule.clone(), span.name.clone()))
+                .or_insert_with(|| SystemStats {
+                    schedule: span.schedule.clone(),
+                    name: span.name.clone(),
+                    mean: Duration::ZERO,
+                    max: Duration::ZERO,
+                    last: Duration::ZERO,
+                });
+            stats.mean += span.duration;
+            stats.max = stats.max.max(span.duration);
+            if Some(frame.index) == newest {
+                stats.last += span.duration;
+            }
+        }
+    }
+    let mut stats: Vec<SystemStats> = totals.into_values().collect();
+    for stats in &mut stats {
+        stats.mean /= frames.len().max(1) as u32;
+    }
+    stats
+}
+
+pub fn profiler_ui(ui: &mut egui::Ui, world: &World, state: &mut ProfilerState) {
+    let Some(profiler) = world.get_resource::<Profiler>() else {
+        ui.label("Add the ProfilerPlugin to profile the systems");
+        return;
+    };
+
+    ui.horizontal(|ui| {
+        let mut paused = profiler.paused();
+        if ui.checkbox(&mut paused, "Pause").changed() {
+            profiler.set_paused(paused);
+        }
+        ui.separator();
+        ui.selectable_value(&mut state.view, ProfilerView::Table, "Table");
+        ui.selectable_value(&mut state.view, ProfilerView::Flame, "Flame");
+        ui.separator();
+        record_ui(ui, profiler, state);
+    });
+    ui.separator();
+
+    profiler.read(|frames| {
+        if frames.iter().all(|frame| frame.spans.is_empty()) {
+            ui.label("No system was timed, enable the `trace` feature of Bevy");
+            return;
+        }
+        match state.view {
+            ProfilerView::Table => stats_table(ui, frames, state),
+            ProfilerView::Flame => flame_view(ui, frames, state),
+        }
+    });
+}
+
+fn record_ui(ui: &mut egui::Ui, profiler: &Profiler, state: &mut ProfilerState) {
+    if let Some((recorded, length)) = profiler.recording_progress() {
+        ui.add(
+            egui::ProgressBar::new(recorded as f32 / length.max(1) as f32)
+                .text(format!("Recording {recorded}/{length} frames"))
+                .desired_width(200.0),
+        );
+        return;
+    }
+    // there is no file system on the web
+    if cfg!(target_arch = "wasm32") {
+        return;
+    }
+    ui.add(
+        egui::DragValue::new(&mut state.record_frames)
+            .clamp_range(1..=100_000)
+            .suffix(" frames"),
+    );
+    ui.add(egui::TextEdit::singleline(&mut state.record_path).desired_width(120.0));
+    if ui
+        .button("Record")
+        .on_hover_text("Record a Chrome trace, open it in chrome://tracing or Perfetto")
+        .clicked()
+        && !state.record_path.trim().is_empty()
+    {
+        let path = PathBuf::from(state.record_path.trim());
+        profiler.record(state.record_frames, path, false);
+    }
+}
+
+fn stats_table(ui: &mut egui::Ui, frames: &VecDeque<ProfiledFrame>, state: &mut ProfilerState) {
+    let mut stats = system_stats(frames);
+    stats.sort_by(|a, b| {
+        let ordering = match state.sort {
+            ProfilerColumn::Schedule => a.schedule.cmp(&b.schedule),
+            ProfilerColumn::System => a.name.cmp(&b.name),
+            ProfilerColumn::Mean => a.mean.cmp(&b.mean),
+            ProfilerColumn::Max => a.max.cmp(&b.max),
+            ProfilerColumn::Last => a.last.cmp(&b.last),
+        };
+        if state.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+    let frame_time =
+        frames.iter().map(|frame| frame.duration).sum::<Duration>() / frames.len().max(1) as u32;
+
+    ui.label(format!(
+        "{} systems over the last {} frames (at most {PROFILED_FRAMES}), {} per frame",
+        stats.len(),
+        frames.len(),
+        format_duration(frame_time)
+    ));
+
+    TableBuilder::new(ui)
+        .striped(true)
+        .resizable(true)
+        .cell_layout(Layout::left_to_right(Align::Center))
+        .column(Column::initial(100.0).at_least(40.0))
+        .column(Column::initial(300.0).at_least(40.0).clip(true))
+        .column(Column::initial(70.0).at_least(40.0))
+        .column(Column::initial(70.0).at_least(40.0))
+        .column(Column::initial(70.0).at_least(40.0))
+        .column(Column::remainder())
+        .min_scrolled_height(0.0)
+        .header(20.0, |mut header| {
+            for column in ProfilerColumn::ALL {
+                header.col(|ui| {
+                    let arrow = match (state.sort == column, state.descending) {
+                        (false, _) => "",
+                        (true, true) => " ⏷",
+                        (true, false) => " ⏶",
+                    };
+                    let label = format!("{}{arrow}", column.label());
+                    if ui.selectable_label(state.sort == column, label).clicked() {
+                        if state.sort == column {
+                            state.descending = !state.descending;
+                        } else {
+                            state.sort = column;
+                            state.descending = !matches!(
+                                column,
+                                ProfilerColumn::Schedule | ProfilerColumn::System
+                            );
+                        }
+                    }
+                });
+            }
+            header.col(|ui| {
+                ui.strong("Frame");
+            });
+        })
+        .body(|body| {
+            body.rows(20.0, stats.len(), |index, mut row| {
+                let stats = &stats[index];
+                row.col(|ui| {
+                    ui.label(stats.schedule.as_ref());
+                });
+                row.col(|ui| {
+                    ui.label(stats.name.as_ref())
+                        .on_hover_text(stats.name.as_ref());
+                });
+                row.col(|ui| {
+                    ui.label(format_duration(stats.mean));
+                });
+                row.col(|ui| {
+                    ui.label(format_duration(stats.max));
+                });
+                row.col(|ui| {
+                    ui.label(format_duration(stats.last));
+                });
+                row.col(|ui| {
+                    let fraction = stats.mean.as_secs_f32() / frame_time.as_secs_f32().max(1e-9);
+                    ui.add(
+                        egui::ProgressBar::new(fraction).text(format!("{:.1}%", fraction * 100.0)),
+                    );
+                });
+            });
+        });
+}
+
+/// Height of a row of the flame view.
+const FLAME_ROW: f32 = 18.0;
+
+/// Spans of a frame on a timeline, one group of rows per thread with the nested spans below
+/// their parent.
+fn flame_view(ui: &mut egui::Ui, frames: &VecDeque<ProfiledFrame>, state: &mut ProfilerState) {
+    state.flame_frame = state.flame_frame.min(frames.len().saturating_sub(1));
+    ui.horizontal(|ui| {
+        ui.label("Frame");
+        ui.add(
+            egui::Slider::new(&mut state.flame_frame, 0..=frames.len().saturating_sub(1))
+                .text("frames ago"),
+        );
+    });
+    let Some(frame) = frames.iter().rev().nth(state.flame_frame) else {
+        return;
+    };
+    ui.label(format!(
+        "Frame {}: {}",
+        frame.index,
+        format_duration(frame.duration)
+    ));
+
+    // depth of each span on its thread, the spans of a thread are strictly nested
+    let mut spans: Vec<_> = frame.spans.iter().collect();
+    spans.sort_by_key(|span| (span.thread, span.start, std::cmp::Reverse(span.duration)));
+    let mut rows: BTreeMap<u64, Vec<(usize, _)>> = BTreeMap::new();
+    let mut open: Vec<Duration> = Vec::new();
+    let mut thread = None;
+    for span in spans {
+        if thread != Some(span.thread) {
+            thread = Some(span.thread);
+            open.clear();
+        }
+        open.retain(|&end| end > span.start);
+        rows.entry(span.thread)
+            .or_default()
+            .push((open.len(), span));
+        open.push(span.start + span.duration);
+    }
+
+    egui::ScrollArea::both().show(ui, |ui| {
+        let width = ui.available_width().max(200.0);
+        let scale = width / frame.duration.as_secs_f32().max(1e-9);
+        for (thread, spans) in rows {
+            ui.label(format!("Thread {thread}"));
+            let depth = spans.iter().map(|(depth, _)| depth + 1).max().unwrap_or(1);
+            let (rect, _) =
+                ui.allocate_exact_size(egui::vec2(width, depth as f32 * FLAME_ROW), Sense::hover());
+            let painter = ui.painter_at(rect);
+            for (depth, span) in spans {
+                let x = (span.start.saturating_sub(frame.start)).as_secs_f32() * scale;
+                let span_rect = egui::Rect::from_min_size(
+                    rect.min + egui::vec2(x, depth as f32 * FLAME_ROW),
+                    egui::vec2(
+                        (span.duration.as_secs_f32() * scale).max(1.0),
+                        FLAME_ROW - 1.0,
+                    ),
+                );
+                painter.rect_filled(span_rect, 0.0, span_color(&span.schedule, span.kind));
+                if span_rect.width() > 30.0 {
+                    painter.with_clip_rect(span_rect.intersect(rect)).text(
+                        span_rect.left_center() + egui::vec2(2.0, 0.0),
+                        egui::Align2::LEFT_CENTER,
+                        span.name.as_ref(),
+                        egui::FontId::monospace(11.0),
+                        Color32::BLACK,
+                    );
+                }
+                if ui.rect_contains_pointer(span_rect) {
+                    egui::show_tooltip_text(
+                        ui.ctx(),
+                        egui::Id::new("flame_tooltip"),
+                        format!(
+                            "{}\n{}: {}",
+                            span.name,
+                            span.schedule,
+                            format_duration(span.duration)
+                        ),
+                    );
+                }
+            }
+        }
+    });
+}
+
+/// Color of a span, the same for the spans of a schedule.
+fn span_color(schedule: &str, kind: SpanKind) -> Color32 {
+    let hash = schedule.bytes().fold(0u32, |hash, byte| {
+        hash.wrapping_mul(31).wrapping_add(byte as u32)
+    });
+    let hue = (hash % 360) as f32 / 360.0;
+    let value = match kind {
+        SpanKind::Schedule => 0.7,
+        SpanKind::System => 0.9,
+    };
+    egui::epaint::Hsva::new(hue, 0.45, value, 1.0).into()
+}
+
+fn format_duration(duration: Duration) -> String {
+    let micros = duration.as_secs_f64() * 1e6;
+    if micros >= 1000.0 {
+        format!("{:.2}ms", micros / 1000.0)
+    } else {
+        format!("{micros:.0}µs")
+    }
+}
@@ -0,0 +1,262 @@
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use bevy_egui::egui::{self, Key, KeyboardShortcut, Modifiers};
+use bevy_inspector_egui::bevy_inspector::hierarchy::SelectedEntities;
+
+use crate::inspector::play_mode::PlayMode;
+use crate::inspector::scene_io::{
+    clear_scene, list_scene_files, load_scene, save_scene, SCENES_DIR, SCENE_EXTENSION,
+};
+use crate::inspector::undo::UndoHistory;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SceneAction {
+    New,
+    Open,
+    Save,
+    SaveAs,
+}
+
+impl SceneAction {
+    pub const ALL: [SceneAction; 4] = [
+        SceneAction::New,
+        SceneAction::Open,
+        SceneAction::Save,
+        SceneAction::SaveAs,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SceneAction::New => "New Scene",
+            SceneAction::Open => "Open Scene…",
+            SceneAction::Save => "Save Scene",
+            SceneAction::SaveAs => "Save As…",
+        }
+    }
+
+    pub fn shortcut(&self) -> KeyboardShortcut {
+        match self {
+            SceneAction::New => KeyboardShortcut::new(Modifiers::CTRL, Key::N),
+            SceneAction::Open => KeyboardShortcut::new(Modifiers::CTRL, Key::O),
+            SceneAction::Save => KeyboardShortcut::new(Modifiers::CTRL, Key::S),
+            SceneAction::SaveAs => {
+                KeyboardShortcut::new(Modifiers::CTRL | Modifiers::SHIFT, Key::S)
+            }
+        }
+    }
+
+    /// Returns the action whose shortcut was pressed this frame, consuming it.
+    ///
+    /// Ignored while a text field has the focus, so it keeps its own shortcuts.
+    pub fn from_shortcuts(ctx: &egui::Context) -> Option<Self> {
+        if ctx.memory(|memory| memory.focus().is_some()) {
+            return None;
+        }
+        ctx.input_mut(|input| {
+            Self::ALL
+                .into_iter()
+                .find(|action| input.consume_shortcut(&action.shortcut()))
+        })
+    }
+}
+
+enum SceneDialog {
+    /// Asked before `action` replaces a scene with unsaved changes.
+    Discard {
+        action: SceneAction,
+    },
+    Open {
+        files: Vec<PathBuf>,
+    },
+    SaveAs {
+        name: String,
+    },
+}
+
+#[derive(Default)]
+pub struct SceneMenuState {
+    pub current_path: Option<PathBuf>,
+    dialog: Option<SceneDialog>,
+    /// [`UndoHistory::position`] when the scene was last saved, loaded or created.
+    saved_position: u64,
+}
+
+impl SceneMenuState {
+    /// `true` if the scene was edited since it was last saved, loaded or created.
+    pub fn unsaved(&self, world: &World) -> bool {
+        world.resource::<UndoHistory>().position() != self.saved_position
+    }
+
+    pub fn run(&mut self, world: &mut World, selected: &mut SelectedEntities, action: SceneAction) {
+        match action {
+            SceneAction::New | SceneAction::Open if self.unsaved(world) => {
+                self.dialog = Some(SceneDialog::Discard { action });
+            }
+            SceneAction::New | SceneAction::Open => self.replace_scene(world, selected, action),
+            SceneAction::Save => match self.current_path.clone() {
+                Some(path) => self.save(world, path),
+                None => self.run(world, selected, SceneAction::SaveAs),
+            },
+            SceneAction::SaveAs => {
+                let name = self
+                    .current_path
+                    .as_ref()
+                    .and_then(|path| path.strip_prefix(SCENES_DIR).ok())
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| format!("scene{SCENE_EXTENSION}"));
+                self.dialog = Some(SceneDialog::SaveAs { name });
+            }
+        }
+    }
+
+    /// Run `New` or `Open`, discarding the current scene.
+    fn replace_scene(
+        &mut self,
+        world: &mut World,
+        selected: &mut SelectedEntities,
+        action: SceneAction,
+    ) {
+        if action == SceneAction::New {
+            PlayMode::stop(world);
+            clear_scene(world);
+            self.reset_history(world);
+            selected.clear();
+            self.current_path = None;
+        } else {
+            self.dialog = Some(SceneDialog::Open {
+                files: list_scene_files(Path::new(SCENES_DIR)),
+            });
+        }
+    }
+
+    /// The steps of the history were made on the replaced scene.
+    fn reset_history(&mut self, world: &mut World) {
+        let mut history = world.resource_mut::<UndoHistory>();
+        history.clear();
+        self.saved_position = history.position();
+    }
+
+    fn save(&mut self, world: &mut World, path: PathBuf) {
+        match save_scene(world, &path) {
+            Ok(()) => {
+                log::info!("Scene saved to {}", path.display());
+                let mut history = world.resource_mut::<UndoHistory>();
+                // the next edits can't be merged into the saved step
+                history.end_merge();
+                self.saved_position = history.position();
+                self.current_path = Some(path);
+            }
+            Err(err) => log::error!("Failed to save scene {}: {err}", path.display()),
+        }
+    }
+
+    fn open(&mut self, world: &mut World, selected: &mut SelectedEntities, path: PathBuf) {
+        PlayMode::stop(world);
+        match load_scene(world, &path) {
+            Ok(()) => {
+                log::info!("Scene loaded from {}", path.display());
+                self.reset_history(world);
+                selected.clear();
+                self.current_path = Some(path);
+            }
+            Err(err) => log::error!("Failed to load scene {}: {err}", path.display()),
+        }
+    }
+
+    /// Draw the "Open Scene" or "Save As" window if one of them is open.
+    pub fn show_dialog(
+        &mut self,
+        ctx: &egui::Context,
+        world: &mut World,
+        selected: &mut SelectedEntities,
+    ) {
+        let Some(dialog) = self.dialog.as_mut() else {
+            return;
+        };
+
+        let mut open = true;
+        let mut picked = None;
+        match dialog {
+            SceneDialog::Discard { action } => {
+                let action = *action;
+                let mut discard = false;
+                egui::Window::new("Unsaved Changes")
+                    .collapsible(false)
+                    .resizable(false)
+                    .open(&mut open)
+                    .show(ctx, |ui| {
+                        ui.label("The scene has unsaved changes, they will be lost.");
+                        ui.horizontal(|ui| {
+                            discard = ui.button("Discard").clicked();
+                            if ui.button("Cancel").clicked() {
+                                self.dialog = None;
+                            }
+                        });
+                    });
+                if discard {
+                    self.dialog = None;
+                    self.replace_scene(world, selected, action);
+                }
+                if self.dialog.is_none() {
+                    return;
+                }
+            }
+            SceneDialog::Open { files } => {
+                egui::Window::new("Open Scene")
+                    .collapsible(false)
+                    .open(&mut open)
+                    .show(ctx, |ui| {
+                        if files.is_empty() {
+                            ui.label(format!("No {SCENE_EXTENSION} files in {SCENES_DIR}/"));
+                        }
+                        egui::ScrollArea::vertical().show(ui, |ui| {
+                            for file in files.iter() {
+                                let name = file.strip_prefix(SCENES_DIR).unwrap_or(file);
+                                if ui.button(name.to_string_lossy()).clicked() {
+                                    picked = Some(file.clone());
+                                }
+                            }
+                        });
+                        ui.separator();
+                        if ui.button("Refresh").clicked() {
+                            *files = list_scene_files(Path::new(SCENES_DIR));
+                        }
+                    });
+                if let Some(path) = picked {
+                    self.open(world, selected, path);
+                    open = false;
+                }
+            }
+            SceneDialog::SaveAs { name } => {
+                egui::Window::new("Save Scene As")
+                    .collapsible(false)
+                    .open(&mut open)
+                    .show(ctx, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{SCENES_DIR}/"));
+                            let response = ui.text_edit_singleline(name);
+                            let submitted = response.lost_focus()
+                                && ui.input(|input| input.key_pressed(Key::Enter));
+                            if (ui.button("Save").clicked() || submitted) && !name.trim().is_empty()
+                            {
+                                let mut file = name.trim().to_string();
+                                if !file.ends_with(SCENE_EXTENSION) {
+                                    file.push_str(SCENE_EXTENSION);
+                                }
+                                picked = Some(Path::new(SCENES_DIR).join(file));
+                            }
+                        });
+                    });
+                if let Some(path) = picked {
+                    self.save(world, path);
+                    open = false;
+                }
+            }
+        }
+
+        if !open {
+            self.dialog = None;
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use bevy_egui::egui::{self, Color32, RichText};
+
+use crate::inspector::schedule_graph::{NodeKind, ScheduleGraphs, ScheduleSnapshot};
+
+pub struct SchedulesState {
+    schedule: Option<String>,
+    filter: String,
+    dot_path: String,
+}
+
+impl Default for SchedulesState {
+    fn default() -> Self {
+        Self {
+            schedule: None,
+            filter: String::new(),
+            dot_path: "schedule.dot".to_string(),
+        }
+    }
+}
+
+pub fn schedules_ui(ui: &mut egui::Ui, world: &mut World, state: &mut SchedulesState) {
+    let mut graphs = world.resource_mut::<ScheduleGraphs>();
+
+    ui.horizontal(|ui| {
+        let selected = state.schedule.as_deref().unwrap_or("");
+        egui::ComboBox::from_id_source("schedules_schedule")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for label in graphs.schedules.keys() {
+                    let checked = state.schedule.as_ref() == Some(label);
+                    if ui.selectable_label(checked, label).clicked() {
+                        state.schedule = Some(label.clone());
+                    }
+                }
+            });
+        let refresh = ui.add_enabled(!graphs.refreshing(), egui::Button::new("⟳ Refresh"));
+        if refresh
+            .on_hover_text("Snapshot the schedules again")
+            .clicked()
+        {
+            graphs.refresh();
+        }
+        ui.separator();
+        ui.add(
+            egui::TextEdit::singleline(&mut state.filter)
+                .hint_text("Filter systems and sets")
+                .desired_width(160.0),
+        );
+    });
+
+    let schedule = state
+        .schedule
+        .as_ref()
+        .filter(|label| graphs.schedules.contains_key(*label))
+        .or_else(|| graphs.schedules.keys().next())
+        .cloned();
+    state.schedule = schedule.clone();
+    let snapshot = schedule.and_then(|label| graphs.schedules.get_key_value(&label));
+    let Some((label, snapshot)) = snapshot else {
+        ui.label("No schedule was snapshotted yet");
+        return;
+    };
+
+    ui.horizontal(|ui| {
+        let systems = snapshot
+            .nodes
+            .iter()
+            .filter(|node| node.kind == NodeKind::System)
+            .count();
+        let edges: usize = snapshot.nodes.iter().map(|node| node.before.len()).sum();
+        ui.label(format!(
+            "{systems} systems, {} sets, {edges} ordering edges",
+            snapshot.nodes.len() - systems
+        ));
+        ui.separator();
+        dot_export_ui(ui, label, snapshot, state);
+    });
+    ui.separator();
+
+    let ambiguous: HashSet<usize> = snapshot
+        .ambiguities
+        .iter()
+        .flat_map(|ambiguity| [ambiguity.first, ambiguity.second])
+        .collect();
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        if !snapshot.ambiguities.is_empty() {
+            let title = RichText::new(format!("⚠ {} ambiguities", snapshot.ambiguities.len()))
+                .color(ui.visuals().warn_fg_color);
+            egui::CollapsingHeader::new(title)
+                .id_source(("schedule_ambiguities", label))
+                .show(ui, |ui| {
+                    for ambiguity in &snapshot.ambiguities {
+                        let access = if ambiguity.components.is_empty() {
+                            "World".to_string()
+                        } else {
+                            ambiguity.components.join(", ")
+                        };
+                        ui.label(format!(
+                            "{} ↔ {}: {access}",
+                            snapshot.nodes[ambiguity.first].name,
+                            snapshot.nodes[ambiguity.second].name
+                        ));
+                    }
+                });
+            ui.separator();
+        }
+
+        let filter = state.filter.trim().to_lowercase();
+        if filter.is_empty() {
+            // the nodes outside of any set, the others are listed under their sets
+            let mut roots: Vec<usize> = (0..snapshot.nodes.len())
+                .filter(|&index| snapshot.nodes[index].parents.is_empty())
+                .collect();
+            roots.sort_by_key(|&index| (snapshot.nodes[index].kind == NodeKind::System, index));
+            for index in roots {
+                node_ui(ui, snapshot, index, &ambiguous);
+            }
+        } else {
+            for (index, node) in snapshot.nodes.iter().enumerate() {
+                if node.name.to_lowercase().contains(&filter) {
+                    node_ui(ui, snapshot, index, &ambiguous);
+                }
+            }
+        }
+    });
+}
+
+/// A system, or a set and its content, with their ordering edges.
+fn node_ui(
+    ui: &mut egui::Ui,
+    snapshot: &ScheduleSnapshot,
+    index: usize,
+    ambiguous: &HashSet<usize>,
+) {
+    let node = &snapshot.nodes[index];
+    let mut name = match node.kind {
+        NodeKind::System => RichText::new(&node.name),
+        NodeKind::Set => RichText::new(format!("{} (set)", node.name)).italics(),
+    };
+    if ambiguous.contains(&index) {
+        name = name.color(ui.visuals().warn_fg_color);
+    }
+    let edges = |ui: &mut egui::Ui| {
+        for (label, others) in [("before", &node.before), ("after", &node.after)] {
+            if others.is_empty() {
+                continue;
+            }
+            let others: Vec<&str> = others
+                .iter()
+                .map(|&other| snapshot.nodes[other].name.as_str())
+                .collect();
+            ui.label(
+                RichText::new(format!("{label} {}", others.join(", ")))
+                    .small()
+                    .color(Color32::GRAY),
+            );
+        }
+    };
+
+    if node.children.is_empty() {
+        ui.label(name);
+        if !node.before.is_empty() || !node.after.is_empty() {
+            ui.indent(("schedule_node_edges", index), edges);
+        }
+        return;
+    }
+    egui::CollapsingHeader::new(name)
+        .id_source(("schedule_node", index))
+        .show(ui, |ui| {
+            edges(ui);
+            let mut children = node.children.clone();
+            children.sort_by_key(|&child| (snapshot.nodes[child].kind == NodeKind::System, child));
+            for child in children {
+                node_ui(ui, snapshot, child, ambiguous);
+            }
+        });
+}
+
+fn dot_export_ui(
+    ui: &mut egui::Ui,
+    label: &str,
+    snapshot: &ScheduleSnapshot,
+    state: &mut SchedulesState,
+) {
+    // there is no file system on the web, the graph can only be copied
+    if cfg!(not(target_arch = "wasm32")) {
+        ui.add(egui::TextEdit::singleline(&mut state.dot_path).desired_width(120.0));
+        if ui.button("Save DOT").clicked() && !state.dot_path.trim().is_empty() {
+            let path = state.dot_path.trim();
+            match std::fs::write(path, snapshot.dot(label)) {
+                Ok(()) => log::info!("Graph of the {label} schedule exported to {path}"),
+                Err(err) => log::error!("Failed to export the graph to {path}: {err}"),
+            }
+        }
+    }
+    if ui.button("Copy DOT").clicked() {
+        let dot = snapshot.dot(label);
+        ui.output_mut(|output| output.copied_text = dot);
+    }
+}
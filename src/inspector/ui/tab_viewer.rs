@@ -1,20 +1,32 @@
+use std::any::TypeId;
+
 use bevy::prelude::*;
+use bevy::render::camera::CameraProjection;
 use bevy::render::render_resource::Extent3d;
 use bevy_egui::egui::{self, TextureId};
-use bevy_inspector_egui::bevy_inspector::hierarchy::SelectedEntities;
+use bevy_inspector_egui::bevy_inspector::hierarchy::{SelectedEntities, SelectionMode};
 use bevy_inspector_egui::bevy_inspector::{
     self, ui_for_entities_shared_components, ui_for_entity_with_children,
 };
+use bevy_reflect::TypeRegistry;
 use egui_gizmo::{GizmoMode, GizmoOrientation};
+use serde::{Deserialize, Serialize};
 
+use crate::inspector::default_scene::MainSceneCamera;
 use crate::inspector::inspect_log::Logs;
+use crate::inspector::overlays::{draw_overlays, OverlaySettings};
+use crate::inspector::picking::{pick_entity, viewport_ray};
+use crate::inspector::undo::{record_inspector_edit, PendingEdit};
 
 use super::{
-    add, draw_console_logs, draw_gizmo, hierarchy_ui, select_asset, select_resource,
-    AddWindowState, GizmoSnapValues, InspectorSelection,
+    add, archetypes_ui, assets_browser_ui, command_line_ui, diagnostics_overlay, diagnostics_ui,
+    draw_console_logs, draw_gizmo, hierarchy_ui, profiler_ui, schedules_ui, select_asset,
+    select_resource, AddWindowState, ArchetypesState, AssetsBrowserState, CommandLineState,
+    ConsoleState, DiagnosticsState, GizmoSnapValues, HierarchyView, InspectorSelection,
+    ProfilerState, SchedulesState,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum EguiWindow {
     SceneView,
     GameView,
@@ -24,11 +36,16 @@ pub enum EguiWindow {
     Assets,
     Inspector,
     Console,
+    Diagnostics,
+    Profiler,
+    Schedules,
+    Archetypes,
 }
 
 pub struct TabViewer<'a> {
     pub world: &'a mut World,
     pub hierarchy_state: &'a AddWindowState<'a>,
+    pub hierarchy_view: &'a mut HierarchyView,
     pub selected_entities: &'a mut SelectedEntities,
     pub selection: &'a mut InspectorSelection,
     pub scene_rect: &'a mut Option<egui::Rect>,
@@ -39,7 +56,15 @@ pub struct TabViewer<'a> {
     pub gizmo_mode: &'a mut GizmoMode,
     pub gizmo_snap: &'a mut GizmoSnapValues,
     pub gizmo_orientation: &'a mut GizmoOrientation,
-    pub filter_level_log: &'a mut log::Level,
+    pub precise_picking: &'a mut bool,
+    pub overlays: &'a mut OverlaySettings,
+    pub console: &'a mut ConsoleState,
+    pub command_line: &'a mut CommandLineState,
+    pub diagnostics: &'a mut DiagnosticsState,
+    pub profiler: &'a mut ProfilerState,
+    pub schedules: &'a mut SchedulesState,
+    pub archetypes: &'a mut ArchetypesState,
+    pub assets_browser: &'a mut AssetsBrowserState,
     pub exist_game_camera: bool,
 }
 
@@ -54,6 +79,7 @@ impl egui_dock::TabViewer for TabViewer<'_> {
             EguiWindow::SceneView => {
                 let rect = ui.clip_rect();
                 *self.scene_rect = Some(rect);
+                let mut scene_response = None;
 
                 if let Some(img) = self.scene_texture_id {
                     if let Some(render) = &self.scene_render {
@@ -65,10 +91,21 @@ impl egui_dock::TabViewer for TabViewer<'_> {
                             ..default()
                         })
                     }
-                    ui.image(img, [rect.size().x - 10., rect.size().y - 32.]);
+                    let response = ui.add(
+                        egui::Image::new(img, [rect.size().x - 10., rect.size().y - 32.])
+                            .sense(egui::Sense::click()),
+                    );
+                    draw_overlays(
+                        ui,
+                        response.rect,
+                        self.world,
+                        *self.overlays,
+                        self.selected_entities,
+                    );
+                    scene_response = Some(response);
                 }
 
-                draw_gizmo(
+                let gizmo_used = draw_gizmo(
                     ui,
                     self.world,
                     self.selected_entities,
@@ -76,6 +113,19 @@ impl egui_dock::TabViewer for TabViewer<'_> {
                     *self.gizmo_orientation,
                     *self.gizmo_snap,
                 );
+
+                if let Some(response) = scene_response.filter(|_| !gizmo_used) {
+                    let selected = pick_ui(
+                        ui,
+                        &response,
+                        self.world,
+                        self.selected_entities,
+                        *self.precise_picking,
+                    );
+                    if selected {
+                        *self.selection = InspectorSelection::Entities;
+                    }
+                }
             }
             EguiWindow::GameView => {
                 let rect = ui.clip_rect();
@@ -89,7 +139,8 @@ impl egui_dock::TabViewer for TabViewer<'_> {
                             ..default()
                         })
                     }
-                    ui.image(img, [rect.size().x - 10., rect.size().y - 32.]);
+                    let response = ui.image(img, [rect.size().x - 10., rect.size().y - 32.]);
+                    diagnostics_overlay(ui, response.rect, self.world, self.diagnostics);
                 }
 
                 if !self.exist_game_camera {
@@ -99,8 +150,13 @@ impl egui_dock::TabViewer for TabViewer<'_> {
                 }
             }
             EguiWindow::Hierarchy => {
-                let selected =
-                    hierarchy_ui(self.world, ui, self.selected_entities, self.hierarchy_state);
+                let selected = hierarchy_ui(
+                    self.world,
+                    ui,
+                    self.selected_entities,
+                    self.hierarchy_state,
+                    self.hierarchy_view,
+                );
                 if selected {
                     *self.selection = InspectorSelection::Entities;
                 }
@@ -110,18 +166,60 @@ impl egui_dock::TabViewer for TabViewer<'_> {
                 select_asset(ui, &type_registry, self.world, self.selection)
             }
             EguiWindow::Assets => {
-                // TODO: implement show assets files
-                ui.label("TODO: implement show assets files");
+                let selected =
+                    assets_browser_ui(ui, self.world, self.assets_browser, self.selected_entities);
+                if selected {
+                    *self.selection = InspectorSelection::Entities;
+                }
             }
             EguiWindow::Console => {
+                let selected = egui::TopBottomPanel::bottom("console_command_line")
+                    .show_inside(ui, |ui| {
+                        command_line_ui(
+                            ui,
+                            self.command_line,
+                            self.world,
+                            self.selected_entities,
+                            self.hierarchy_state,
+                        )
+                    })
+                    .inner;
+                if selected {
+                    *self.selection = InspectorSelection::Entities;
+                }
                 let logs = self.world.resource::<Logs>();
-                draw_console_logs(ui, self.filter_level_log, logs.clone());
+                draw_console_logs(ui, self.console, logs.clone());
+            }
+            EguiWindow::Diagnostics => diagnostics_ui(ui, self.world, self.diagnostics),
+            EguiWindow::Profiler => profiler_ui(ui, self.world, self.profiler),
+            EguiWindow::Schedules => schedules_ui(ui, self.world, self.schedules),
+            EguiWindow::Archetypes => {
+                let selected =
+                    archetypes_ui(ui, self.world, self.archetypes, self.selected_entities);
+                if selected {
+                    *self.selection = InspectorSelection::Entities;
+                }
             }
             EguiWindow::Inspector => match *self.selection {
                 InspectorSelection::Entities => match self.selected_entities.as_slice() {
                     &[] => {}
-                    &[entity] => ui_for_entity_with_children(self.world, entity, ui),
-                    entities => ui_for_entities_shared_components(self.world, entities, ui),
+                    &[entity] => {
+                        record_inspector_edit(self.world, ui, &[entity], |world, ui| {
+                            ui_for_entity_with_children(world, entity, ui)
+                        });
+                        ui.separator();
+                        add_ui(ui, &[entity], self.world, self.hierarchy_state);
+                        remove_ui(ui, &[entity], self.world, &type_registry);
+                    }
+                    entities => {
+                        let entities = entities.to_vec();
+                        record_inspector_edit(self.world, ui, &entities, |world, ui| {
+                            ui_for_entities_shared_components(world, &entities, ui)
+                        });
+                        ui.separator();
+                        add_ui(ui, &entities, self.world, self.hierarchy_state);
+                        remove_ui(ui, &entities, self.world, &type_registry);
+                    }
                 },
                 InspectorSelection::Resource(type_id, ref name) => {
                     ui.label(name);
@@ -154,29 +252,135 @@ impl egui_dock::TabViewer for TabViewer<'_> {
     fn clear_background(&self, window: &Self::Tab) -> bool {
         !matches!(window, EguiWindow::SceneView | EguiWindow::GameView)
     }
+
+    // the Hierarchy scrolls its rows itself, drawing only the visible ones
+    fn scroll_bars(&self, window: &Self::Tab) -> [bool; 2] {
+        let scroll = !matches!(window, EguiWindow::Hierarchy);
+        [scroll, scroll]
+    }
+}
+
+/// Select the entity under the cursor when the scene image is clicked.
+///
+/// Returns `true` if the entity selection was changed.
+fn pick_ui(
+    ui: &egui::Ui,
+    response: &egui::Response,
+    world: &mut World,
+    selected_entities: &mut SelectedEntities,
+    precise: bool,
+) -> bool {
+    if !response.clicked() {
+        return false;
+    }
+    let Some(pointer) = response.interact_pointer_pos() else {
+        return false;
+    };
+
+    let Ok((camera_transform, projection)) = world
+        .query_filtered::<(&GlobalTransform, &Projection), With<MainSceneCamera>>()
+        .get_single(world)
+    else {
+        return false;
+    };
+    let uv = (pointer - response.rect.min) / response.rect.size();
+    let ndc = Vec2::new(uv.x * 2. - 1., 1. - uv.y * 2.);
+    let Some(ray) = viewport_ray(camera_transform, projection.get_projection_matrix(), ndc) else {
+        return false;
+    };
+
+    let mode = ui
+        .input(|input| SelectionMode::from_ctrl_shift(input.modifiers.ctrl, input.modifiers.shift));
+    match pick_entity(world, ray, precise) {
+        // there is no order between the entities of the scene to extend the selection with
+        Some(entity) => selected_entities.select(mode, entity, |_, entity| [entity]),
+        None if matches!(mode, SelectionMode::Replace) => selected_entities.clear(),
+        None => return false,
+    }
+    true
 }
 
-//
-// TODO: add component to entity
-// TODO: get components from world registry and add to AddWindowState
-//
-#[allow(unused)]
 fn add_ui(
     ui: &mut egui::Ui,
     entities: &[Entity],
     world: &mut World,
-    add_window_state: Option<&AddWindowState>,
+    add_window_state: &AddWindowState,
 ) {
-    if let Some(add_window_state) = add_window_state {
-        let layout = egui::Layout::top_down(egui::Align::Center).with_cross_justify(true);
-        ui.with_layout(layout, |ui| {
-            ui.menu_button("+", |ui| {
-                if let Some(add_item) = add::add_ui(ui, add_window_state) {
-                    for entity in entities {
-                        add_item.add_to_entity(world, *entity);
-                    }
+    let layout = egui::Layout::top_down(egui::Align::Center).with_cross_justify(true);
+    ui.with_layout(layout, |ui| {
+        ui.menu_button("+ Add Component", |ui| {
+            if let Some(add_item) = add::add_ui(ui, add_window_state) {
+                let edit = PendingEdit::begin(
+                    world,
+                    format!("Add {}", add_item.name()),
+                    entities.iter().copied(),
+                );
+                for entity in entities {
+                    add_item.add_to_entity(world, *entity);
                 }
-            });
+                edit.commit(world);
+            }
         });
+    });
+}
+
+/// List the reflected components shared by all the `entities`, with a button to remove them.
+fn remove_ui(
+    ui: &mut egui::Ui,
+    entities: &[Entity],
+    world: &mut World,
+    type_registry: &TypeRegistry,
+) {
+    let Some(first) = entities
+        .first()
+        .and_then(|&entity| world.get_entity(entity))
+    else {
+        return;
+    };
+
+    // the hierarchy components are edited from the Hierarchy tab
+    let hierarchy = [TypeId::of::<Parent>(), TypeId::of::<Children>()];
+    let mut components: Vec<_> = first
+        .archetype()
+        .components()
+        .filter_map(|component_id| {
+            let type_id = world.components().get_info(component_id)?.type_id()?;
+            if hierarchy.contains(&type_id) {
+                return None;
+            }
+            let registration = type_registry.get(type_id)?;
+            let reflect_component = registration.data::<ReflectComponent>()?;
+            entities
+                .iter()
+                .all(|&entity| {
+                    world
+                        .get_entity(entity)
+                        .is_some_and(|entity| reflect_component.contains(entity))
+                })
+                .then(|| (registration.short_name(), reflect_component))
+        })
+        .collect();
+    components.sort_by_key(|(name, _)| *name);
+
+    let mut remove = None;
+    ui.collapsing("Components", |ui| {
+        for (name, reflect_component) in components {
+            ui.horizontal(|ui| {
+                ui.label(name);
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.small_button("×").on_hover_text("Remove").clicked() {
+                        remove = Some((name, reflect_component));
+                    }
+                });
+            });
+        }
+    });
+
+    if let Some((name, reflect_component)) = remove {
+        let edit = PendingEdit::begin(world, format!("Remove {name}"), entities.iter().copied());
+        for &entity in entities {
+            reflect_component.remove(&mut world.entity_mut(entity));
+        }
+        edit.commit(world);
     }
 }
@@ -0,0 +1,241 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+
+use bevy_egui::egui;
+use serde::{Serialize, Serializer};
+
+use crate::inspector::inspect_log::LogItem;
+
+use super::console_logs::format_time;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    JsonLines,
+    Text,
+}
+
+impl ExportFormat {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::JsonLines => "JSON Lines",
+            ExportFormat::Text => "Plain text",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::JsonLines => "jsonl",
+            ExportFormat::Text => "txt",
+        }
+    }
+
+    /// One line per row, each row being a log and its number of occurrences.
+    pub fn write(&self, rows: &[(LogItem, usize)]) -> String {
+        let mut output = String::new();
+        for (log, count) in rows {
+            let count = *count;
+            match self {
+                ExportFormat::JsonLines => write_json(&mut output, log, count),
+                ExportFormat::Text => write_text(&mut output, log, count),
+            }
+            output.push('\n');
+        }
+        output
+    }
+}
+
+pub struct ExportDialog {
+    format: ExportFormat,
+    path: String,
+}
+
+impl Default for ExportDialog {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::JsonLines,
+            path: format!("logs.{}", ExportFormat::JsonLines.extension()),
+        }
+    }
+}
+
+/// Draw the "Export Logs" window, returns `false` once it is closed.
+///
+/// The `rows` to export are only copied from the logs when they are saved or copied.
+pub fn export_dialog(
+    ctx: &egui::Context,
+    dialog: &mut ExportDialog,
+    row_count: usize,
+    rows: impl Fn() -> Vec<(LogItem, usize)>,
+) -> bool {
+    let mut open = true;
+    let mut done = false;
+    egui::Window::new("Export Logs")
+        .collapsible(false)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.label(format!("{row_count} visible logs"));
+            ui.horizontal(|ui| {
+                for format in [ExportFormat::JsonLines, ExportFormat::Text] {
+                    let previous = dialog.format;
+                    if ui
+                        .radio_value(&mut dialog.format, format, format.label())
+                        .changed()
+                    {
+                        let stem = dialog
+                            .path
+                            .strip_suffix(previous.extension())
+                            .and_then(|stem| stem.strip_suffix('.'));
+                        if let Some(stem) = stem {
+                            dialog.path = format!("{stem}.{}", format.extension());
+                        }
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                // there is no file system on the web, the logs can only be copied
+                if cfg!(not(target_arch = "wasm32")) {
+                    ui.text_edit_singleline(&mut dialog.path);
+                    if ui.button("Save").clicked() && !dialog.path.trim().is_empty() {
+                        let path = dialog.path.trim();
+                        let rows = rows();
+                        match std::fs::write(path, dialog.format.write(&rows)) {
+                            Ok(()) => {
+                                log::info!("{} logs exported to {path}", rows.len());
+                                done = true;
+                            }
+                            Err(err) => log::error!("Failed to export the logs to {path}: {err}"),
+                        }
+                    }
+                }
+                if ui.button("Copy").clicked() {
+                    let text = dialog.format.write(&rows());
+                    ui.output_mut(|output| output.copied_text = text);
+                    done = true;
+                }
+            });
+        });
+    open && !done
+}
+
+/// A log in the JSON Lines export.
+#[derive(Serialize)]
+struct JsonRow<'a> {
+    sequence: u64,
+    /// Seconds since the unix epoch.
+    time: f64,
+    frame: u32,
+    thread: Option<&'a str>,
+    level: &'a str,
+    target: &'a str,
+    module: &'a str,
+    file: &'a str,
+    line: Option<u32>,
+    details: &'a str,
+    #[serde(serialize_with = "serialize_fields")]
+    fields: &'a [(String, String)],
+    count: usize,
+}
+
+/// Key/values as a JSON object of strings, in their order.
+fn serialize_fields<S: Serializer>(
+    fields: &&[(String, String)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(fields.iter().map(|(key, value)| (key, value)))
+}
+
+fn write_json(output: &mut String, log: &LogItem, count: usize) {
+    let row = JsonRow {
+        sequence: log.sequence,
+        time: log.time.as_secs_f64(),
+        frame: log.frame,
+        thread: log.thread.as_deref(),
+        level: log.level_log.as_str(),
+        target: &log.target,
+        module: &log.module,
+        file: &log.file,
+        line: log.line.parse().ok(),
+        details: &log.details,
+        fields: &log.fields,
+        count,
+    };
+    // strings and numbers always serialize
+    if let Ok(json) = serde_json::to_string(&row) {
+        output.push_str(&json);
+    }
+}
+
+fn write_text(output: &mut String, log: &LogItem, count: usize) {
+    let _ = write!(
+        output,
+        "{} [{}] {:<5} {} {}:{} {}",
+        format_time(log.time),
+        log.frame,
+        log.level_log,
+        log.target,
+        log.file,
+        log.line,
+        single_line(&log.details)
+    );
+    for (key, value) in &log.fields {
+        let _ = write!(output, " {key}={}", single_line(value));
+    }
+    if count > 1 {
+        let _ = write!(output, " (x{count})");
+    }
+}
+
+/// `text` with its line breaks escaped, to keep one line per log.
+fn single_line(text: &str) -> Cow<'_, str> {
+    if text.contains(['\n', '\r']) {
+        Cow::Owned(text.replace('\r', "\\r").replace('\n', "\\n"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn item() -> LogItem {
+        LogItem {
+            sequence: 7,
+            time: Duration::from_secs(1_700_000_000),
+            frame: 42,
+            thread: None,
+            level_log: log::Level::Warn,
+            target: "game::enemy".to_string(),
+            module: "game::enemy".to_string(),
+            file: "src/enemy.rs".to_string(),
+            line: "12".to_string(),
+            details: "hit \"goblin\"\nat the gate".to_string(),
+            fields: vec![("health".to_string(), "3".to_string())],
+        }
+    }
+
+    #[test]
+    fn json_lines_are_valid_json() {
+        let output = ExportFormat::JsonLines.write(&[(item(), 2), (item(), 1)]);
+        let rows: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["details"], "hit \"goblin\"\nat the gate");
+        assert_eq!(rows[0]["fields"]["health"], "3");
+        assert_eq!(rows[0]["line"], 12);
+        assert_eq!(rows[0]["thread"], serde_json::Value::Null);
+        assert_eq!(rows[0]["count"], 2);
+    }
+
+    #[test]
+    fn text_has_one_line_per_log() {
+        let output = ExportFormat::Text.write(&[(item(), 2), (item(), 1)]);
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("hit \"goblin\"\\nat the gate health=3 (x2)"));
+    }
+}
@@ -1,45 +1,406 @@
-use bevy_egui::egui::{Align, Color32, Layout, Ui};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+use bevy_egui::egui::{self, Align, Color32, Layout, Ui};
 use egui_extras::{Column, TableBuilder};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::inspector::inspect_log::{LogFilter, LogItem, Logs};
+
+use super::console_export::{export_dialog, ExportDialog};
 
-use crate::inspector::inspect_log::Logs;
+pub struct ConsoleState {
+    pub levels: LevelToggles,
+    /// Command opening a source location, see [`EDITOR_PRESETS`].
+    pub editor_command: String,
+    /// Show identical messages once, with their number of occurrences.
+    pub collapse_duplicates: bool,
+    /// Text searched in the details, file and module of the logs.
+    search: String,
+    /// Search with a regular expression instead of the plain text.
+    regex: bool,
+    /// Regex compiled from `search`, or the error of the compilation.
+    compiled: Option<(String, Result<Regex, String>)>,
+    /// Only the logs with all these fields are shown.
+    field_filters: Vec<FieldFilter>,
+    /// Sequence of the logs whose fields are shown.
+    expanded: HashSet<u64>,
+    /// Text of the target filter being edited.
+    directives: String,
+    directives_error: Option<String>,
+    export: Option<ExportDialog>,
+    shown: ShownLogs,
+}
 
-use super::Selectable;
+impl Default for ConsoleState {
+    fn default() -> Self {
+        Self {
+            levels: LevelToggles::default(),
+            editor_command: EDITOR_PRESETS[0].1.to_string(),
+            collapse_duplicates: false,
+            search: String::new(),
+            regex: false,
+            compiled: None,
+            field_filters: Vec::new(),
+            expanded: HashSet::new(),
+            directives: String::new(),
+            directives_error: None,
+            export: None,
+            shown: ShownLogs::default(),
+        }
+    }
+}
 
-pub fn draw_console_logs(ui: &mut Ui, filter: &mut log::Level, logs: Logs) {
-    let map_logs = logs.get_logs();
+const ROW_HEIGHT: f32 = 30.0;
+/// Height of each field of an expanded row.
+const FIELD_HEIGHT: f32 = 18.0;
+
+/// Commands opening a file at a line, `{file}` and `{line}` are replaced by the location.
+pub const EDITOR_PRESETS: [(&str, &str); 5] = [
+    ("VS Code", "code -g {file}:{line}"),
+    ("Zed", "zed {file}:{line}"),
+    ("Sublime Text", "subl {file}:{line}"),
+    ("IntelliJ IDEA", "idea --line {line} {file}"),
+    ("Emacs", "emacsclient -n +{line} {file}"),
+];
+
+/// Levels shown in the Console.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelToggles {
+    pub error: bool,
+    pub warn: bool,
+    pub info: bool,
+    pub debug: bool,
+    pub trace: bool,
+}
+
+impl Default for LevelToggles {
+    fn default() -> Self {
+        Self {
+            error: true,
+            warn: true,
+            info: true,
+            debug: true,
+            trace: true,
+        }
+    }
+}
+
+impl LevelToggles {
+    pub fn get_mut(&mut self, level: log::Level) -> &mut bool {
+        match level {
+            log::Level::Error => &mut self.error,
+            log::Level::Warn => &mut self.warn,
+            log::Level::Info => &mut self.info,
+            log::Level::Debug => &mut self.debug,
+            log::Level::Trace => &mut self.trace,
+        }
+    }
+
+    pub fn shown(&self, level: log::Level) -> bool {
+        match level {
+            log::Level::Error => self.error,
+            log::Level::Warn => self.warn,
+            log::Level::Info => self.info,
+            log::Level::Debug => self.debug,
+            log::Level::Trace => self.trace,
+        }
+    }
+}
+
+/// Logs with a structured field, with this value if it is set.
+#[derive(Clone, PartialEq, Eq)]
+struct FieldFilter {
+    key: String,
+    value: Option<String>,
+}
+
+impl FieldFilter {
+    fn matches(&self, log: &LogItem) -> bool {
+        log.fields.iter().any(|(key, value)| {
+            *key == self.key && self.value.iter().all(|expected| expected == value)
+        })
+    }
+}
+
+impl std::fmt::Display for FieldFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{} = {value}", self.key),
+            None => write!(f, "has {}", self.key),
+        }
+    }
+}
+
+/// Logs matching the search of the Console, case insensitive for the plain text.
+enum Search<'a> {
+    Everything,
+    Text(String),
+    Regex(&'a Regex),
+}
+
+impl Search<'_> {
+    fn matches(&self, log: &LogItem) -> bool {
+        let mut fields = [&log.details, &log.file, &log.module]
+            .into_iter()
+            .chain(log.fields.iter().flat_map(|(key, value)| [key, value]));
+        match self {
+            Search::Everything => true,
+            Search::Text(text) => fields.any(|field| field.to_lowercase().contains(text.as_str())),
+            Search::Regex(regex) => fields.any(|field| regex.is_match(field)),
+        }
+    }
+}
+
+/// Everything deciding which logs are shown, the rows are rebuilt when it changes.
+#[derive(Clone, PartialEq)]
+struct ShownFilter {
+    levels: LevelToggles,
+    targets: LogFilter,
+    search: String,
+    regex: bool,
+    field_filters: Vec<FieldFilter>,
+    collapse_duplicates: bool,
+}
+
+/// Level, module, location, details and fields of a log, identical for its duplicates.
+type DuplicateKey = (
+    log::Level,
+    String,
+    String,
+    String,
+    String,
+    Vec<(String, String)>,
+);
+
+fn duplicate_key(log: &LogItem) -> DuplicateKey {
+    (
+        log.level_log,
+        log.module.clone(),
+        log.file.clone(),
+        log.line.clone(),
+        log.details.clone(),
+        log.fields.clone(),
+    )
+}
+
+/// One row of the Console, a log and its duplicates when they are collapsed.
+struct ShownRow {
+    /// Sequence of the displayed log.
+    sequence: u64,
+    /// Sequences of the later occurrences.
+    duplicates: VecDeque<u64>,
+    /// Set when the duplicates are collapsed.
+    key: Option<DuplicateKey>,
+    /// Number of fields of the log, for the height of the expanded row.
+    fields: usize,
+}
+
+/// Rows of the logs matching the filters of the Console, by sequence.
+///
+/// Only the records logged since the last frame are filtered, and the rows of the dropped ones
+/// removed, instead of filtering every stored log each frame.
+#[derive(Default)]
+struct ShownLogs {
+    filter: Option<ShownFilter>,
+    /// Sequence of the next record to filter.
+    next_sequence: u64,
+    /// Ordered by sequence.
+    rows: VecDeque<ShownRow>,
+    /// Sequence of the row of each collapsed message.
+    duplicates: HashMap<DuplicateKey, u64>,
+}
+
+impl ShownLogs {
+    fn update(&mut self, logs: &Logs, filter: ShownFilter, search: &Search) {
+        if self.filter.as_ref() != Some(&filter) {
+            *self = ShownLogs::default();
+        }
+        self.remove_dropped(logs);
 
+        let rows = &mut self.rows;
+        let duplicates = &mut self.duplicates;
+        self.next_sequence = logs.for_each_since(self.next_sequence, |log| {
+            let shown = filter.levels.shown(log.level_log)
+                && filter.targets.enabled(&log.target, log.level_log)
+                && search.matches(log)
+                && filter.field_filters.iter().all(|field| field.matches(log));
+            if !shown {
+                return;
+            }
+            let mut row = ShownRow {
+                sequence: log.sequence,
+                duplicates: VecDeque::new(),
+                key: None,
+                fields: log.fields.len(),
+            };
+            if !filter.collapse_duplicates {
+                rows.push_back(row);
+                return;
+            }
+            let key = duplicate_key(log);
+            match duplicates.get(&key) {
+                Some(&sequence) => {
+                    let index = rows.partition_point(|row| row.sequence < sequence);
+                    rows[index].duplicates.push_back(log.sequence);
+                }
+                None => {
+                    duplicates.insert(key.clone(), log.sequence);
+                    row.key = Some(key);
+                    rows.push_back(row);
+                }
+            }
+        });
+        self.filter = Some(filter);
+    }
+
+    /// Remove the rows of the logs dropped from the storage, a collapsed row moves to its next
+    /// occurrence.
+    fn remove_dropped(&mut self, logs: &Logs) {
+        let first = logs.first_sequence();
+        while self.rows.front().is_some_and(|row| row.sequence < first) {
+            let Some(mut row) = self.rows.pop_front() else {
+                break;
+            };
+            let next = row.duplicates.pop_front();
+            let Some(key) = row.key.as_ref() else {
+                continue;
+            };
+            let Some(next) = next else {
+                self.duplicates.remove(key);
+                continue;
+            };
+            self.duplicates.insert(key.clone(), next);
+            row.sequence = next;
+            let index = self.rows.partition_point(|other| other.sequence < next);
+            self.rows.insert(index, row);
+        }
+    }
+}
+
+/// The search being typed, `Err` if it is an invalid regex.
+fn search<'a>(
+    text: &str,
+    regex: bool,
+    compiled: &'a mut Option<(String, Result<Regex, String>)>,
+) -> Result<Search<'a>, String> {
+    if text.is_empty() {
+        return Ok(Search::Everything);
+    }
+    if !regex {
+        return Ok(Search::Text(text.to_lowercase()));
+    }
+    if !matches!(compiled, Some((source, _)) if source == text) {
+        let regex = Regex::new(text).map_err(|err| err.to_string());
+        *compiled = Some((text.to_string(), regex));
+    }
+    match compiled {
+        Some((_, Ok(regex))) => Ok(Search::Regex(regex)),
+        Some((_, Err(err))) => Err(err.clone()),
+        None => Ok(Search::Everything),
+    }
+}
+
+pub fn draw_console_logs(ui: &mut Ui, state: &mut ConsoleState, logs: Logs) {
     ui.horizontal(|ui| {
-        if let Some(s) = Selectable::new(
-            &[
-                ("trace", log::Level::Trace, None),
-                ("info", log::Level::Info, None),
-                ("warn", log::Level::Warn, None),
-                ("error", log::Level::Error, None),
-            ],
-            *filter,
-            14,
-            0,
-            Color32::DARK_GRAY,
-        )
-        .show(ui)
-        {
-            *filter = s;
+        for level in log::Level::iter() {
+            let count = logs.count(level);
+            let shown = state.levels.get_mut(level);
+            if ui
+                .add(
+                    egui::Button::new(level.as_str().to_lowercase())
+                        .fill(if *shown {
+                            Color32::DARK_GRAY
+                        } else {
+                            Color32::TRANSPARENT
+                        })
+                        .rounding(egui::Rounding::none()),
+                )
+                .on_hover_text(format!("{count} stored"))
+                .clicked()
+            {
+                *shown = !*shown;
+            }
+        }
+        ui.checkbox(&mut state.collapse_duplicates, "Collapse");
+        targets_menu(ui, state, &logs);
+        if cfg!(not(target_arch = "wasm32")) {
+            editor_menu(ui, &mut state.editor_command);
         }
         ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
             if ui.button("Clear").clicked() {
                 logs.clear();
             }
+            if ui.button("Export…").clicked() {
+                state.export.get_or_insert_with(ExportDialog::default);
+            }
         });
     });
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut state.regex, ".*")
+            .on_hover_text("Regular expression");
+        ui.add(
+            egui::TextEdit::singleline(&mut state.search)
+                .hint_text("Search details, file, module and fields")
+                .desired_width(f32::INFINITY),
+        );
+    });
+
+    if !state.field_filters.is_empty() {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Fields:");
+            state.field_filters.retain(|filter| {
+                !ui.button(format!("{filter} ✖"))
+                    .on_hover_text("Remove the filter")
+                    .clicked()
+            });
+        });
+    }
+
+    let search = match search(&state.search, state.regex, &mut state.compiled) {
+        Ok(search) => search,
+        Err(err) => {
+            ui.colored_label(Color32::RED, err);
+            Search::Everything
+        }
+    };
 
     ui.separator();
 
+    let filter = ShownFilter {
+        levels: state.levels,
+        targets: logs.filter(),
+        search: state.search.clone(),
+        regex: state.regex,
+        field_filters: state.field_filters.clone(),
+        collapse_duplicates: state.collapse_duplicates,
+    };
+    state.shown.update(&logs, filter, &search);
+    let rows = &state.shown.rows;
+
+    if let Some(dialog) = &mut state.export {
+        let export_rows = || {
+            rows.iter()
+                .filter_map(|row| Some((logs.get(row.sequence)?, row.duplicates.len() + 1)))
+                .collect()
+        };
+        if !export_dialog(ui.ctx(), dialog, rows.len(), export_rows) {
+            state.export = None;
+        }
+    }
+
     let table = TableBuilder::new(ui)
         .striped(true)
-        .resizable(!map_logs.is_empty())
+        .resizable(!rows.is_empty())
+        .stick_to_bottom(true)
         .cell_layout(Layout::left_to_right(Align::Center))
         .column(Column::initial(30.0).resizable(false))
+        .column(Column::initial(90.0).at_least(40.0))
+        .column(Column::initial(50.0).at_least(30.0))
         .column(Column::initial(50.0).resizable(false))
+        .column(Column::initial(80.0).at_least(40.0))
         .column(Column::initial(100.0).at_least(40.0))
         .column(Column::remainder().clip(true))
         .min_scrolled_height(0.0);
@@ -49,9 +410,18 @@ pub fn draw_console_logs(ui: &mut Ui, filter: &mut log::Level, logs: Logs) {
             header.col(|ui| {
                 ui.strong("");
             });
+            header.col(|ui| {
+                ui.strong("Time").on_hover_text("UTC");
+            });
+            header.col(|ui| {
+                ui.strong("Frame");
+            });
             header.col(|ui| {
                 ui.strong("Level");
             });
+            header.col(|ui| {
+                ui.strong("Thread");
+            });
             header.col(|ui| {
                 ui.strong("File");
             });
@@ -59,29 +429,398 @@ pub fn draw_console_logs(ui: &mut Ui, filter: &mut log::Level, logs: Logs) {
                 ui.strong("Details");
             });
         })
-        .body(|mut body| {
-            for (log, n) in &map_logs {
-                if *filter < log.level_log {
-                    continue;
+        .body(|body| {
+            let editor_command = &state.editor_command;
+            let heights = rows.iter().map(|row| {
+                if state.expanded.contains(&row.sequence) {
+                    ROW_HEIGHT + FIELD_HEIGHT * row.fields as f32
+                } else {
+                    ROW_HEIGHT
+                }
+            });
+            let heights: Vec<f32> = heights.collect();
+            body.heterogeneous_rows(heights.into_iter(), |index, mut row| {
+                // only the rows in view are copied from the logs
+                let shown = &rows[index];
+                let count = shown.duplicates.len() + 1;
+                let Some(item) = logs.get(shown.sequence) else {
+                    return;
+                };
+                let log = &item;
+                row.col(|ui| {
+                    if count > 1 {
+                        ui.label(count.to_string());
+                    }
+                });
+                row.col(|ui| {
+                    ui.label(format_time(log.time))
+                        .on_hover_text(format!("#{}", log.sequence));
+                });
+                row.col(|ui| {
+                    ui.label(log.frame.to_string());
+                });
+                row.col(|ui| {
+                    ui.label(log.level_log.to_string());
+                });
+                row.col(|ui| {
+                    ui.label(log.thread.as_deref().unwrap_or_default());
+                });
+                row.col(|ui| {
+                    location_ui(ui, log, editor_command);
+                });
+                row.col(|ui| {
+                    details_ui(ui, log, &mut state.expanded, &mut state.field_filters);
+                });
+            });
+        });
+}
+
+/// The message of a log, followed by its fields when it is expanded.
+fn details_ui(
+    ui: &mut Ui,
+    log: &LogItem,
+    expanded: &mut HashSet<u64>,
+    field_filters: &mut Vec<FieldFilter>,
+) {
+    if log.fields.is_empty() {
+        ui.label(&log.details);
+        return;
+    }
+
+    let open = expanded.contains(&log.sequence);
+    ui.vertical(|ui| {
+        ui.horizontal(|ui| {
+            if ui.small_button(if open { "⏷" } else { "⏵" }).clicked() {
+                if open {
+                    expanded.remove(&log.sequence);
+                } else {
+                    expanded.insert(log.sequence);
                 }
-                body.row(30., |mut row| {
-                    row.col(|ui| {
-                        if n >= &99 {
-                            ui.label("+99");
-                        } else if n > &0 {
-                            ui.label(n.to_string());
+            }
+            ui.label(&log.details);
+            if !open {
+                let fields: Vec<String> = log
+                    .fields
+                    .iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect();
+                ui.weak(fields.join(" "));
+            }
+        });
+        if !open {
+            return;
+        }
+        for (key, value) in &log.fields {
+            ui.horizontal(|ui| {
+                ui.add_space(ui.spacing().indent);
+                ui.add(egui::Label::new(format!("{key} = {value}")).sense(egui::Sense::click()))
+                    .on_hover_text("Right click to filter by this field")
+                    .context_menu(|ui| {
+                        let filters = [
+                            FieldFilter {
+                                key: key.clone(),
+                                value: Some(value.clone()),
+                            },
+                            FieldFilter {
+                                key: key.clone(),
+                                value: None,
+                            },
+                        ];
+                        for filter in filters {
+                            if ui.button(format!("Only {filter}")).clicked() {
+                                if !field_filters.contains(&filter) {
+                                    field_filters.push(filter);
+                                }
+                                ui.close_menu();
+                            }
                         }
                     });
-                    row.col(|ui| {
-                        ui.label(log.level_log.to_string());
-                    });
-                    row.col(|ui| {
-                        ui.label(format!("{}:{}", log.file, log.line));
-                    });
-                    row.col(|ui| {
-                        ui.label(&log.details);
+            });
+        }
+    });
+}
+
+/// `file:line` of a log, opening it in the editor when clicked.
+fn location_ui(ui: &mut Ui, log: &LogItem, editor_command: &str) {
+    let location = format!("{}:{}", log.file, log.line);
+    // there is no editor to open on the web
+    let response = if cfg!(target_arch = "wasm32") || log.file.is_empty() {
+        ui.add(egui::Label::new(&location).sense(egui::Sense::click()))
+            .on_hover_text(&log.target)
+    } else {
+        ui.link(&location)
+            .on_hover_text(format!("{}\nClick to open in the editor", log.target))
+    };
+    if response.clicked() && cfg!(not(target_arch = "wasm32")) && !log.file.is_empty() {
+        open_in_editor(editor_command, &log.file, &log.line);
+    }
+    response.context_menu(|ui| {
+        if ui.button("Copy location").clicked() {
+            ui.output_mut(|output| output.copied_text = location);
+            ui.close_menu();
+        }
+    });
+}
+
+/// Run the editor command with the location replaced in each of its arguments.
+fn open_in_editor(command: &str, file: &str, line: &str) {
+    let mut args = command
+        .split_whitespace()
+        .map(|arg| arg.replace("{file}", file).replace("{line}", line));
+    let Some(program) = args.next() else {
+        log::warn!("No editor command set in the Console");
+        return;
+    };
+    if let Err(err) = std::process::Command::new(&program).args(args).spawn() {
+        log::error!("Failed to open {file}:{line} with `{program}`: {err}");
+    }
+}
+
+fn editor_menu(ui: &mut Ui, editor_command: &mut String) {
+    ui.menu_button("Editor", |ui| {
+        for (name, command) in EDITOR_PRESETS {
+            if ui
+                .selectable_label(editor_command == command, name)
+                .on_hover_text(command)
+                .clicked()
+            {
+                *editor_command = command.to_string();
+                ui.close_menu();
+            }
+        }
+        ui.separator();
+        ui.add(
+            egui::TextEdit::singleline(editor_command)
+                .hint_text("editor {file}:{line}")
+                .desired_width(220.0),
+        )
+        .on_hover_text("{file} and {line} are replaced by the location of the log");
+    });
+}
+
+/// Menu editing the level of each log target, as a tree of their modules.
+fn targets_menu(ui: &mut Ui, state: &mut ConsoleState, logs: &Logs) {
+    ui.menu_button("Targets", |ui| {
+        let mut filter = logs.filter();
+        let original = filter.clone();
+
+        ui.horizontal(|ui| {
+            if ui.button("Game crate only").clicked() {
+                filter = LogFilter::game_only();
+            }
+            if ui.button("Everything").clicked() {
+                filter = LogFilter::parse("trace").unwrap_or_default();
+            }
+        });
+
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut state.directives)
+                .hint_text("warn,game=trace")
+                .desired_width(f32::INFINITY),
+        );
+        if response.lost_focus() {
+            match LogFilter::parse(&state.directives) {
+                Ok(parsed) => {
+                    filter = parsed;
+                    state.directives_error = None;
+                }
+                Err(err) => state.directives_error = Some(err),
+            }
+        }
+        if let Some(err) = &state.directives_error {
+            ui.colored_label(Color32::RED, err);
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Default");
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                egui::ComboBox::from_id_source("log_default_level")
+                    .selected_text(filter.default.as_str())
+                    .width(80.0)
+                    .show_ui(ui, |ui| {
+                        for level in log::LevelFilter::iter() {
+                            ui.selectable_value(&mut filter.default, level, level.as_str());
+                        }
                     });
-                });
+            });
+        });
+        egui::ScrollArea::vertical()
+            .max_height(300.0)
+            .show(ui, |ui| {
+                let tree = TargetNode::build(&logs.targets());
+                for (name, node) in &tree.children {
+                    target_tree_ui(ui, name, name, node, &mut filter);
+                }
+            });
+
+        if filter != original {
+            logs.set_filter(filter);
+        }
+        if !response.has_focus() {
+            state.directives = logs.filter().to_string();
+        }
+    });
+}
+
+/// Targets grouped by their `::` separated segments.
+#[derive(Default)]
+struct TargetNode {
+    children: BTreeMap<String, TargetNode>,
+}
+
+impl TargetNode {
+    fn build(targets: &BTreeSet<String>) -> Self {
+        let mut root = TargetNode::default();
+        for target in targets {
+            let mut node = &mut root;
+            for segment in target.split("::") {
+                node = node.children.entry(segment.to_string()).or_default();
+            }
+        }
+        root
+    }
+}
+
+fn target_tree_ui(ui: &mut Ui, path: &str, name: &str, node: &TargetNode, filter: &mut LogFilter) {
+    let row = |ui: &mut Ui, filter: &mut LogFilter| {
+        ui.label(name);
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            directive_combo(ui, path, filter);
+        });
+    };
+
+    if node.children.is_empty() {
+        ui.horizontal(|ui| {
+            // align with the headers of the collapsing nodes
+            ui.add_space(ui.spacing().indent);
+            row(ui, filter);
+        });
+        return;
+    }
+
+    let id = ui.make_persistent_id(("log_target", path));
+    egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, false)
+        .show_header(ui, |ui| row(ui, filter))
+        .body(|ui| {
+            for (name, child) in &node.children {
+                target_tree_ui(ui, &format!("{path}::{name}"), name, child, filter);
+            }
+        });
+}
+
+fn directive_combo(ui: &mut Ui, target: &str, filter: &mut LogFilter) {
+    let mut directive = filter.directive(target);
+    let inherited = |filter: &LogFilter| {
+        let mut inherited = filter.clone();
+        inherited.set_directive(target, None);
+        inherited.level_for(target)
+    };
+    let text = match directive {
+        Some(level) => level.as_str().to_string(),
+        None => format!("({})", inherited(filter).as_str()),
+    };
+
+    egui::ComboBox::from_id_source(("log_directive", target))
+        .selected_text(text)
+        .width(80.0)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut directive, None, "Inherit");
+            for level in log::LevelFilter::iter() {
+                ui.selectable_value(&mut directive, Some(level), level.as_str());
             }
         });
+    if directive != filter.directive(target) {
+        filter.set_directive(target, directive);
+    }
+}
+
+/// `HH:MM:SS.mmm` of a time since the unix epoch.
+pub(super) fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        seconds / 3600 % 24,
+        seconds / 60 % 60,
+        seconds % 60,
+        time.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inspector::inspect_log::game_crate;
+
+    fn log(logs: &Logs, target: &str, level: log::Level, message: &str) {
+        log::Log::log(
+            logs,
+            &log::Record::builder()
+                .target(target)
+                .level(level)
+                .args(format_args!("{message}"))
+                .build(),
+        );
+    }
+
+    fn filter(targets: &str, collapse_duplicates: bool) -> ShownFilter {
+        ShownFilter {
+            levels: LevelToggles {
+                debug: false,
+                ..LevelToggles::default()
+            },
+            targets: LogFilter::parse(targets).unwrap(),
+            search: String::new(),
+            regex: false,
+            field_filters: Vec::new(),
+            collapse_duplicates,
+        }
+    }
+
+    fn shown(shown: &ShownLogs, logs: &Logs) -> Vec<(String, usize)> {
+        let rows = shown.rows.iter();
+        rows.map(|row| {
+            (
+                logs.get(row.sequence).unwrap().details,
+                row.duplicates.len(),
+            )
+        })
+        .collect()
+    }
+
+    #[test]
+    fn rows_follow_the_filter_and_the_new_logs() {
+        let logs = Logs::default();
+        logs.set_filter(LogFilter::parse("trace").unwrap());
+        let game = game_crate();
+        log(&logs, game, log::Level::Info, "spawned");
+        log(&logs, game, log::Level::Debug, "moved");
+        log(&logs, "wgpu", log::Level::Warn, "slow");
+        log(&logs, game, log::Level::Info, "spawned");
+
+        let mut rows = ShownLogs::default();
+        rows.update(&logs, filter(game, false), &Search::Everything);
+        let spawned = ("spawned".to_string(), 0);
+        assert_eq!(shown(&rows, &logs), [spawned.clone(), spawned.clone()]);
+
+        // changing the filter rebuilds the rows
+        rows.update(&logs, filter("warn", true), &Search::Everything);
+        assert_eq!(shown(&rows, &logs), [("slow".to_string(), 0)]);
+        rows.update(&logs, filter("trace", true), &Search::Everything);
+        let collapsed = [("spawned".to_string(), 1), ("slow".to_string(), 0)];
+        assert_eq!(shown(&rows, &logs), collapsed);
+
+        // only the new logs are added
+        log(&logs, game, log::Level::Info, "spawned");
+        log(&logs, game, log::Level::Error, "died");
+        rows.update(&logs, filter("trace", true), &Search::Everything);
+        let added = ("died".to_string(), 0);
+        let collapsed = [("spawned".to_string(), 2), ("slow".to_string(), 0), added];
+        assert_eq!(shown(&rows, &logs), collapsed);
+
+        // the rows of the cleared logs are removed
+        logs.clear();
+        rows.update(&logs, filter("trace", true), &Search::Everything);
+        assert!(rows.rows.is_empty());
+    }
 }
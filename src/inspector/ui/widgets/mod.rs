@@ -1,5 +1,6 @@
+mod console_export;
 mod console_logs;
 mod selectable;
 
-pub use console_logs::draw_console_logs;
+pub use console_logs::{draw_console_logs, ConsoleState, LevelToggles};
 pub use selectable::Selectable;
@@ -0,0 +1,596 @@
+use bevy::asset::HandleUntyped;
+use bevy::ecs::component::Tick;
+use bevy::hierarchy::despawn_with_children_recursive;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy_egui::egui;
+use bevy_inspector_egui::bevy_inspector::guess_entity_name;
+use bevy_reflect::TypeRegistry;
+
+use super::asset_handles::{contains_handle, strengthen_handles, strong_handles};
+
+/// Maximum number of steps kept in the undo stack.
+const MAX_HISTORY: usize = 100;
+
+/// Components of an entity, `None` if the entity doesn't exist.
+type EntityState = Option<CapturedEntity>;
+
+struct CapturedEntity {
+    components: Vec<Box<dyn Reflect>>,
+    /// Names of the components that aren't reflected, they are lost if the entity is despawned.
+    unreflected: Vec<String>,
+}
+
+/// State of a set of entities at some point in time.
+///
+/// Only components registered with `#[reflect(Component)]` are captured.
+#[derive(Default)]
+pub struct EntitiesSnapshot {
+    entities: Vec<(Entity, EntityState)>,
+    /// Strong handles of the captured components, their assets must still exist when restored.
+    handles: Vec<HandleUntyped>,
+}
+
+impl EntitiesSnapshot {
+    pub fn capture(world: &World, entities: impl IntoIterator<Item = Entity>) -> Self {
+        let type_registry = world.resource::<AppTypeRegistry>().read();
+        let mut snapshot = EntitiesSnapshot::default();
+        let mut captured = HashSet::new();
+        for entity in entities {
+            if captured.insert(entity) {
+                let state = capture_entity(world, entity, &type_registry, &mut snapshot.handles);
+                snapshot.entities.push((entity, state));
+            }
+        }
+        snapshot
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.entities.iter().any(|(e, _)| *e == entity)
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entities.iter().map(|(entity, _)| *entity)
+    }
+
+    /// Put the entities back in their captured state, the entities that still exist are kept.
+    pub fn restore(&self, world: &mut World) {
+        let type_registry = world.resource::<AppTypeRegistry>().clone();
+        let type_registry = type_registry.read();
+        for (entity, state) in &self.entities {
+            restore_entity(world, *entity, state, &type_registry);
+        }
+        strengthen_handles(world, self.entities());
+    }
+}
+
+fn capture_entity(
+    world: &World,
+    entity: Entity,
+    type_registry: &TypeRegistry,
+    handles: &mut Vec<HandleUntyped>,
+) -> EntityState {
+    let entity_ref = world.get_entity(entity)?;
+    let mut captured = CapturedEntity {
+        components: Vec::new(),
+        unreflected: Vec::new(),
+    };
+    for component_id in entity_ref.archetype().components() {
+        let Some(info) = world.components().get_info(component_id) else {
+            continue;
+        };
+        let component = info
+            .type_id()
+            .and_then(|type_id| type_registry.get_type_data::<ReflectComponent>(type_id))
+            .and_then(|reflect_component| reflect_component.reflect(entity_ref));
+        match component {
+            Some(component) => {
+                strong_handles(type_registry, component, handles);
+                captured.components.push(component.clone_value());
+            }
+            None => captured.unreflected.push(info.name().to_string()),
+        }
+    }
+    Some(captured)
+}
+
+fn restore_entity(
+    world: &mut World,
+    entity: Entity,
+    state: &EntityState,
+    type_registry: &TypeRegistry,
+) {
+    let Some(captured) = state else {
+        if world.get_entity(entity).is_some() {
+            despawn_with_children_recursive(world, entity);
+        }
+        return;
+    };
+    let components = &captured.components;
+
+    let current = capture_entity(world, entity, type_registry, &mut Vec::new());
+    if current.is_none() && !captured.unreflected.is_empty() {
+        log::warn!(
+            "{entity:?} is restored without its components that aren't reflected: {}",
+            captured.unreflected.join(", ")
+        );
+    }
+    let current = current
+        .map(|current| current.components)
+        .unwrap_or_default();
+    let Some(mut entity_mut) = world.get_or_spawn(entity) else {
+        log::warn!("Can't restore {entity:?}: its id is used by another entity");
+        return;
+    };
+
+    // applying a handle would only change its id, the current handles are replaced instead
+    for component in current.iter().filter(|current| {
+        contains_handle(&***current)
+            || components
+                .iter()
+                .all(|component| component.type_name() != current.type_name())
+    }) {
+        if let Some(reflect_component) = type_registry
+            .get_with_name(component.type_name())
+            .and_then(|registration| registration.data::<ReflectComponent>())
+        {
+            reflect_component.remove(&mut entity_mut);
+        }
+    }
+
+    for component in components {
+        if let Some(reflect_component) = type_registry
+            .get_with_name(component.type_name())
+            .and_then(|registration| registration.data::<ReflectComponent>())
+        {
+            reflect_component.apply_or_insert(&mut entity_mut, &**component);
+        }
+    }
+}
+
+struct HistoryEntry {
+    /// Unique in the history, see [`UndoHistory::position`].
+    id: u64,
+    name: String,
+    before: EntitiesSnapshot,
+    after: EntitiesSnapshot,
+    merge_key: Option<egui::Id>,
+}
+
+/// Undo and redo stacks of the edits made through the inspector.
+#[derive(Resource, Default)]
+pub struct UndoHistory {
+    undo: Vec<HistoryEntry>,
+    redo: Vec<HistoryEntry>,
+    /// `true` while the last entry can still absorb edits with the same merge key.
+    merging: bool,
+    /// Length of the undo stack to come back to with [`UndoHistory::rollback`].
+    checkpoint: Option<usize>,
+    last_id: u64,
+    /// Widget focused while merging, the merge ends when the focus moves.
+    merge_focus: Option<egui::Id>,
+}
+
+impl UndoHistory {
+    /// Add a new step to the history, discarding the redo stack.
+    pub fn push(
+        &mut self,
+        name: impl Into<String>,
+        mut before: EntitiesSnapshot,
+        after: EntitiesSnapshot,
+    ) {
+        // entities that only exist in `after` were spawned by this step
+        for entity in after.entities() {
+            if !before.contains(entity) {
+                before.entities.push((entity, None));
+            }
+        }
+
+        // the steps undone past the checkpoint are replaced, a rollback clears the whole history
+        if self
+            .checkpoint
+            .is_some_and(|checkpoint| self.undo.len() < checkpoint)
+        {
+            self.checkpoint = Some(0);
+        }
+        self.redo.clear();
+        self.merging = false;
+        self.last_id += 1;
+        self.undo.push(HistoryEntry {
+            id: self.last_id,
+            name: name.into(),
+            before,
+            after,
+            merge_key: None,
+        });
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0);
+            if let Some(checkpoint) = &mut self.checkpoint {
+                *checkpoint = checkpoint.saturating_sub(1);
+            }
+        }
+    }
+
+    /// Like [`UndoHistory::push`], but continuous edits sharing the same `key` (e.g. a gizmo drag)
+    /// are merged into a single step until [`UndoHistory::end_merge`] is called.
+    pub fn push_merged(
+        &mut self,
+        key: egui::Id,
+        name: impl Into<String>,
+        before: EntitiesSnapshot,
+        after: EntitiesSnapshot,
+    ) {
+        if let Some(last) = self.undo.last_mut() {
+            if self.merging && last.merge_key == Some(key) {
+                last.after = after;
+                return;
+            }
+        }
+
+        self.push(name, before, after);
+        if let Some(last) = self.undo.last_mut() {
+            last.merge_key = Some(key);
+        }
+        self.merging = true;
+    }
+
+    /// Stop merging new edits into the last step.
+    pub fn end_merge(&mut self) {
+        self.merging = false;
+    }
+
+    /// Stop merging at the end of a continuous edit: when the pointer is released, or when the
+    /// focus moves, e.g. from a text field to another one.
+    pub fn end_merge_on_input(&mut self, ctx: &egui::Context) {
+        let focus = ctx.memory(|memory| memory.focus());
+        let (released, down) =
+            ctx.input(|input| (input.pointer.any_released(), input.pointer.any_down()));
+        if released || focus != self.merge_focus || (!down && focus.is_none()) {
+            self.end_merge();
+        }
+        self.merge_focus = focus;
+    }
+
+    /// Remember the current position in the history, to come back to it with
+    /// [`UndoHistory::rollback`].
+    pub fn checkpoint(&mut self) {
+        self.checkpoint = Some(self.undo.len());
+    }
+
+    /// Forget the steps done after the checkpoint, without reverting them: the world must be back
+    /// in its state at the checkpoint.
+    ///
+    /// The steps undone past the checkpoint are put back on the undo stack. If they were replaced
+    /// by new steps, the history doesn't match the world anymore and is cleared.
+    pub fn rollback(&mut self) {
+        let Some(checkpoint) = self.checkpoint.take() else {
+            return;
+        };
+        while self.undo.len() < checkpoint {
+            let Some(entry) = self.redo.pop() else {
+                break;
+            };
+            self.undo.push(entry);
+        }
+        self.undo.truncate(checkpoint);
+        self.redo.clear();
+        self.merging = false;
+    }
+
+    /// Forget every step, when the edited world is replaced.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.merging = false;
+        self.checkpoint = self.checkpoint.map(|_| 0);
+    }
+
+    /// Identifies the state of the world reached through the history, `0` before any step.
+    ///
+    /// The last step can still change while merging edits, see [`UndoHistory::end_merge`].
+    pub fn position(&self) -> u64 {
+        self.undo.last().map_or(0, |entry| entry.id)
+    }
+
+    pub fn undo_name(&self) -> Option<&str> {
+        self.undo.last().map(|entry| entry.name.as_str())
+    }
+
+    pub fn redo_name(&self) -> Option<&str> {
+        self.redo.last().map(|entry| entry.name.as_str())
+    }
+
+    /// Revert the last step. Returns `false` if there was nothing to undo.
+    pub fn undo(world: &mut World) -> bool {
+        let mut history = world.resource_mut::<UndoHistory>();
+        history.merging = false;
+        let Some(entry) = history.undo.pop() else {
+            return false;
+        };
+
+        entry.before.restore(world);
+        log::info!("Undo: {}", entry.name);
+        world.resource_mut::<UndoHistory>().redo.push(entry);
+        true
+    }
+
+    /// Apply again the last undone step. Returns `false` if there was nothing to redo.
+    pub fn redo(world: &mut World) -> bool {
+        let mut history = world.resource_mut::<UndoHistory>();
+        history.merging = false;
+        let Some(entry) = history.redo.pop() else {
+            return false;
+        };
+
+        entry.after.restore(world);
+        log::info!("Redo: {}", entry.name);
+        world.resource_mut::<UndoHistory>().undo.push(entry);
+        true
+    }
+}
+
+/// Helper to record an edit of the world as one undo step.
+///
+/// ```ignore
+/// let mut edit = PendingEdit::begin(world, "Add Child", [parent]);
+/// let child = world.spawn_empty().set_parent(parent).id();
+/// edit.track(child);
+/// edit.commit(world);
+/// ```
+pub struct PendingEdit {
+    name: String,
+    before: EntitiesSnapshot,
+    entities: Vec<Entity>,
+}
+
+impl PendingEdit {
+    pub fn begin(
+        world: &World,
+        name: impl Into<String>,
+        entities: impl IntoIterator<Item = Entity>,
+    ) -> Self {
+        let entities: Vec<_> = entities.into_iter().collect();
+        Self {
+            name: name.into(),
+            before: EntitiesSnapshot::capture(world, entities.iter().copied()),
+            entities,
+        }
+    }
+
+    /// Rename the edit, when its name depends on what it did.
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.name = name.into();
+    }
+
+    /// Track an entity spawned after [`PendingEdit::begin`].
+    pub fn track(&mut self, entity: Entity) {
+        self.entities.push(entity);
+    }
+
+    pub fn commit(self, world: &mut World) {
+        let after = EntitiesSnapshot::capture(world, self.entities);
+        world
+            .resource_mut::<UndoHistory>()
+            .push(self.name, self.before, after);
+    }
+}
+
+/// Run `inspector_ui` for `entities` and record the fields it changed as one undo step.
+///
+/// Continuous edits (dragging a value, typing in a text field) are merged together.
+pub fn record_inspector_edit(
+    world: &mut World,
+    ui: &mut egui::Ui,
+    entities: &[Entity],
+    inspector_ui: impl FnOnce(&mut World, &mut egui::Ui),
+) {
+    // values only change through the pointer in the inspector, including a drag that started in
+    // it, or the keyboard in a focused widget: flying the camera or dragging a gizmo is skipped
+    let rect = ui.clip_rect().intersect(ui.max_rect());
+    let hovered = ui.rect_contains_pointer(rect);
+    let focused = ui.memory(|memory| memory.focus().is_some());
+    let interacting = ui.input(|input| {
+        let pressed_here = input
+            .pointer
+            .press_origin()
+            .is_some_and(|pos| rect.contains(pos));
+        let pointer =
+            (input.pointer.any_down() || input.pointer.any_released()) && (hovered || pressed_here);
+        let keyboard = input.events.iter().any(|event| {
+            matches!(
+                event,
+                egui::Event::Key { .. } | egui::Event::Text(_) | egui::Event::Paste(_)
+            )
+        });
+        pointer || focused && keyboard
+    });
+    if !interacting {
+        inspector_ui(world, ui);
+        return;
+    }
+
+    let mut tracked = entities.to_vec();
+    let mut index = 0;
+    while let Some(&entity) = tracked.get(index) {
+        if let Some(children) = world.get::<Children>(entity) {
+            tracked.extend(children.iter().copied());
+        }
+        index += 1;
+    }
+
+    let before = EntitiesSnapshot::capture(world, tracked.iter().copied());
+    let last_run = world.increment_change_tick();
+    inspector_ui(world, ui);
+    let this_run = world.read_change_tick();
+
+    let changed: Vec<_> = tracked
+        .into_iter()
+        .filter(|&entity| changed_since(world, entity, last_run, this_run))
+        .collect();
+    if changed.is_empty() {
+        return;
+    }
+
+    let before = EntitiesSnapshot {
+        entities: before
+            .entities
+            .into_iter()
+            .filter(|(entity, _)| changed.contains(entity))
+            .collect(),
+        handles: before.handles,
+    };
+    let after = EntitiesSnapshot::capture(world, changed.iter().copied());
+    let name = match changed.as_slice() {
+        &[entity] => format!("Edit {}", guess_entity_name(world, entity)),
+        entities => format!("Edit {} entities", entities.len()),
+    };
+    world.resource_mut::<UndoHistory>().push_merged(
+        egui::Id::new(("inspector", changed)),
+        name,
+        before,
+        after,
+    );
+}
+
+fn changed_since(world: &World, entity: Entity, last_run: Tick, this_run: Tick) -> bool {
+    let Some(entity_ref) = world.get_entity(entity) else {
+        return false;
+    };
+    let mut components = entity_ref.archetype().components();
+    components.any(|component_id| {
+        entity_ref
+            .get_change_ticks_by_id(component_id)
+            .is_some_and(|ticks| ticks.is_changed(last_run, this_run))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world() -> (World, Entity) {
+        let mut world = World::new();
+        world.init_resource::<AppTypeRegistry>();
+        world.init_resource::<UndoHistory>();
+        world
+            .resource::<AppTypeRegistry>()
+            .write()
+            .register::<Name>();
+        let entity = world.spawn(Name::new("a")).id();
+        (world, entity)
+    }
+
+    /// Rename `entity` as one step of the history.
+    fn rename(world: &mut World, entity: Entity, name: &str) {
+        let edit = PendingEdit::begin(world, format!("Rename {name}"), [entity]);
+        world.entity_mut(entity).insert(Name::new(name.to_string()));
+        edit.commit(world);
+    }
+
+    fn name(world: &World, entity: Entity) -> Option<&str> {
+        world.get::<Name>(entity).map(|name| name.as_str())
+    }
+
+    fn undo_steps(world: &World) -> usize {
+        world.resource::<UndoHistory>().undo.len()
+    }
+
+    #[test]
+    fn undo_and_redo_restore_the_entities() {
+        let (mut world, entity) = world();
+        rename(&mut world, entity, "b");
+        let mut edit = PendingEdit::begin(&world, "Spawn", []);
+        let spawned = world.spawn(Name::new("c")).id();
+        edit.track(spawned);
+        edit.commit(&mut world);
+
+        assert!(UndoHistory::undo(&mut world));
+        assert!(world.get_entity(spawned).is_none());
+        assert!(UndoHistory::undo(&mut world));
+        assert_eq!(name(&world, entity), Some("a"));
+        assert!(!UndoHistory::undo(&mut world));
+
+        assert!(UndoHistory::redo(&mut world));
+        assert_eq!(name(&world, entity), Some("b"));
+        assert_eq!(world.resource::<UndoHistory>().redo_name(), Some("Spawn"));
+        assert!(UndoHistory::redo(&mut world));
+        assert_eq!(name(&world, spawned), Some("c"));
+        assert!(!UndoHistory::redo(&mut world));
+
+        // a new step discards the redo stack
+        UndoHistory::undo(&mut world);
+        rename(&mut world, entity, "d");
+        assert!(!UndoHistory::redo(&mut world));
+    }
+
+    #[test]
+    fn merged_edits_are_undone_at_once() {
+        let (mut world, entity) = world();
+        let key = egui::Id::new("drag");
+        for name in ["b", "c", "d"] {
+            let before = EntitiesSnapshot::capture(&world, [entity]);
+            world.entity_mut(entity).insert(Name::new(name));
+            let after = EntitiesSnapshot::capture(&world, [entity]);
+            let mut history = world.resource_mut::<UndoHistory>();
+            history.push_merged(key, "Drag", before, after);
+        }
+        assert_eq!(undo_steps(&world), 1);
+
+        // the merge ends with the drag
+        world.resource_mut::<UndoHistory>().end_merge();
+        let before = EntitiesSnapshot::capture(&world, [entity]);
+        world.entity_mut(entity).insert(Name::new("e"));
+        let after = EntitiesSnapshot::capture(&world, [entity]);
+        let mut history = world.resource_mut::<UndoHistory>();
+        history.push_merged(key, "Drag", before, after);
+        assert_eq!(undo_steps(&world), 2);
+
+        UndoHistory::undo(&mut world);
+        assert_eq!(name(&world, entity), Some("d"));
+        UndoHistory::undo(&mut world);
+        assert_eq!(name(&world, entity), Some("a"));
+        UndoHistory::redo(&mut world);
+        assert_eq!(name(&world, entity), Some("d"));
+    }
+
+    #[test]
+    fn rollback_forgets_the_steps_after_the_checkpoint() {
+        let (mut world, entity) = world();
+        rename(&mut world, entity, "b");
+        let checkpoint = world.resource::<UndoHistory>().position();
+        world.resource_mut::<UndoHistory>().checkpoint();
+        rename(&mut world, entity, "c");
+        assert_ne!(world.resource::<UndoHistory>().position(), checkpoint);
+        world.resource_mut::<UndoHistory>().rollback();
+        assert_eq!(world.resource::<UndoHistory>().position(), checkpoint);
+        assert!(!UndoHistory::redo(&mut world));
+
+        // the steps undone past the checkpoint are put back
+        world.resource_mut::<UndoHistory>().checkpoint();
+        UndoHistory::undo(&mut world);
+        assert_eq!(world.resource::<UndoHistory>().position(), 0);
+        world.resource_mut::<UndoHistory>().rollback();
+        assert_eq!(world.resource::<UndoHistory>().position(), checkpoint);
+
+        // unless they were replaced, then the history no longer matches the world
+        world.resource_mut::<UndoHistory>().checkpoint();
+        UndoHistory::undo(&mut world);
+        rename(&mut world, entity, "d");
+        world.resource_mut::<UndoHistory>().rollback();
+        assert_eq!(undo_steps(&world), 0);
+    }
+
+    #[test]
+    fn the_history_is_bounded() {
+        let (mut world, entity) = world();
+        let mut positions = HashSet::new();
+        for i in 0..MAX_HISTORY + 10 {
+            rename(&mut world, entity, &i.to_string());
+            assert!(positions.insert(world.resource::<UndoHistory>().position()));
+        }
+        assert_eq!(undo_steps(&world), MAX_HISTORY);
+        while UndoHistory::undo(&mut world) {}
+        // the oldest steps were dropped
+        assert_eq!(name(&world, entity), Some("9"));
+    }
+}